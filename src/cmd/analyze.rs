@@ -0,0 +1,192 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+use anyhow::Result;
+use itertools::Itertools;
+
+use crate::types::{
+    config::Config, flight::Flight, flight_data::FlightData, AirlineName, AirportCode,
+};
+
+/// A pair of airports, with the lower code first
+type Pair = (AirportCode, AirportCode);
+
+fn pair(a: &AirportCode, b: &AirportCode) -> Pair {
+    if a < b {
+        (a.clone(), b.clone())
+    } else {
+        (b.clone(), a.clone())
+    }
+}
+
+/// A pair of airports that no airline flies between
+pub struct Underserved {
+    pub airports: Pair,
+    /// The number of flights at each airport of the pair, by any airline
+    pub flights: (usize, usize),
+    /// The airports of the pair where the airline has no gates
+    pub needs_gates: Vec<AirportCode>,
+}
+
+/// How a plan covers the air network in Gatelogue
+pub struct Analysis {
+    /// Airports that the plan flies to
+    pub direct: BTreeSet<AirportCode>,
+    /// Airports that the plan doesn't fly to, but that another airline flies to
+    /// from an airport the plan flies to
+    pub one_connection: BTreeSet<AirportCode>,
+    /// Airports that cannot be reached with at most one connection
+    pub unreached: BTreeSet<AirportCode>,
+    /// Pairs of airports that the plan flies between, and the other airlines that also do
+    pub duplicated: BTreeMap<Pair, BTreeSet<AirlineName>>,
+    /// The pairs of airports in the same world without any flights, with the fewest flights
+    /// at either airport first
+    pub underserved: Vec<Underserved>,
+}
+
+/// Analyses the coverage of `plan`, keeping the `top` most underserved pairs
+pub fn analyze(
+    config: &mut Config,
+    fd: &FlightData,
+    plan: &[Flight],
+    top: usize,
+) -> Result<Analysis> {
+    let gated = config.airports()?;
+
+    // `fd` only has the flights of other airlines once preprocessed
+    let mut flown = BTreeMap::<Pair, BTreeSet<AirlineName>>::new();
+    let mut flights_at = BTreeMap::<AirportCode, usize>::new();
+    for f in &fd.flights {
+        for (a, b) in f
+            .airports
+            .iter()
+            .tuple_combinations()
+            .filter(|(a, b)| a != b)
+        {
+            flown
+                .entry(pair(a, b))
+                .or_default()
+                .insert(f.airline.clone());
+        }
+        for a in f.airports.iter().unique() {
+            *flights_at.entry(a.clone()).or_default() += 1;
+        }
+    }
+    let mut planned = BTreeSet::new();
+    for f in plan {
+        for ((a, _), (b, _)) in f
+            .stops()
+            .tuple_combinations()
+            .filter(|((a, _), (b, _))| a != b)
+        {
+            planned.insert(pair(a, b));
+        }
+        for (a, _) in f.stops().unique_by(|(a, _)| a) {
+            *flights_at.entry(a.clone()).or_default() += 1;
+        }
+    }
+
+    let direct = plan
+        .iter()
+        .flat_map(|f| f.stops().map(|(a, _)| a.clone()))
+        .collect::<BTreeSet<_>>();
+    let one_connection = flown
+        .keys()
+        .filter_map(|(a, b)| {
+            if direct.contains(a) {
+                Some(b)
+            } else if direct.contains(b) {
+                Some(a)
+            } else {
+                None
+            }
+        })
+        .filter(|a| !direct.contains(*a))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    let unreached = fd
+        .old_world_airports
+        .iter()
+        .chain(&fd.new_world_airports)
+        .filter(|a| !direct.contains(*a) && !one_connection.contains(*a))
+        .cloned()
+        .collect();
+    let duplicated = planned
+        .iter()
+        .filter_map(|p| Some((p.clone(), flown.get(p)?.clone())))
+        .collect();
+
+    let count = |a: &AirportCode| flights_at.get(a).copied().unwrap_or_default();
+    let underserved = [&fd.old_world_airports, &fd.new_world_airports]
+        .into_iter()
+        .flat_map(|world| world.iter().sorted().dedup().tuple_combinations())
+        .filter(|(a, b)| {
+            // pairs between airports with gates are up to the planner
+            let p = pair(a, b);
+            !(flown.contains_key(&p)
+                || planned.contains(&p)
+                || (gated.contains(a) && gated.contains(b)))
+        })
+        .map(|(a, b)| Underserved {
+            airports: pair(a, b),
+            flights: (count(a), count(b)),
+            needs_gates: [a, b]
+                .into_iter()
+                .filter(|a| !gated.contains(a))
+                .cloned()
+                .collect(),
+        })
+        .sorted_by_key(|u| {
+            (
+                u.flights.0 + u.flights.1,
+                u.needs_gates.len(),
+                u.airports.clone(),
+            )
+        })
+        .take(top)
+        .collect();
+
+    Ok(Analysis {
+        direct,
+        one_connection,
+        unreached,
+        duplicated,
+        underserved,
+    })
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "==Coverage==")?;
+        for (name, airports) in [
+            ("Direct", &self.direct),
+            ("One connection", &self.one_connection),
+            ("Unreached", &self.unreached),
+        ] {
+            writeln!(
+                f,
+                "{name} ({}): {}",
+                airports.len(),
+                airports.iter().join(", ")
+            )?;
+        }
+
+        writeln!(f, "==Duplicated Routes==")?;
+        for ((a, b), airlines) in &self.duplicated {
+            writeln!(f, "{a}-{b}: {}", airlines.iter().join(", "))?;
+        }
+
+        writeln!(f, "==Underserved Pairs==")?;
+        for u in &self.underserved {
+            let ((a, b), (n1, n2)) = (&u.airports, u.flights);
+            writeln!(
+                f,
+                "{a}-{b}: {n1} flights at {a}, {n2} at {b} (needs gates at {})",
+                u.needs_gates.join(" and ")
+            )?;
+        }
+        Ok(())
+    }
+}
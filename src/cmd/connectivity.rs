@@ -0,0 +1,117 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Display,
+};
+
+use anyhow::Result;
+use itertools::Itertools;
+
+use crate::types::{config::Config, flight::Flight, AirportCode};
+
+/// The airports and the ones each can fly to without transferring, in a plan
+pub struct Network {
+    links: BTreeMap<AirportCode, BTreeSet<AirportCode>>,
+}
+
+impl Network {
+    /// The network of `flights` between `airports`. Every pair of stops of a flight is linked,
+    /// as passengers can stay on a multi-stop flight
+    pub fn new(airports: &[AirportCode], flights: &[Flight]) -> Self {
+        let mut links = airports
+            .iter()
+            .map(|a| (a.clone(), BTreeSet::new()))
+            .collect::<BTreeMap<_, _>>();
+        for f in flights {
+            for ((a, _), (b, _)) in f.stops().tuple_combinations() {
+                if a != b {
+                    links.entry(a.clone()).or_default().insert(b.clone());
+                    links.entry(b.clone()).or_default().insert(a.clone());
+                }
+            }
+        }
+        Self { links }
+    }
+    /// Whether `a` has a flight to `b`
+    pub fn linked(&self, a: &AirportCode, b: &AirportCode) -> bool {
+        self.links.get(a).is_some_and(|l| l.contains(b))
+    }
+    /// The number of flights needed to get from `from` to each airport it can reach
+    pub fn hops(&self, from: &AirportCode) -> BTreeMap<AirportCode, usize> {
+        let mut hops = BTreeMap::from([(from.clone(), 0)]);
+        let mut queue = VecDeque::from([from.clone()]);
+        while let Some(a) = queue.pop_front() {
+            let n = hops[&a] + 1;
+            for b in self.links.get(&a).into_iter().flatten() {
+                if !hops.contains_key(b) {
+                    hops.insert(b.clone(), n);
+                    queue.push_back(b.clone());
+                }
+            }
+        }
+        hops
+    }
+}
+
+/// A way in which a plan does not meet `connectivity`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Violation {
+    /// The airport, which is not a hub, has no flight to a hub
+    NoHub(AirportCode),
+    /// Travelling between the airports needs this many transfers, or cannot be done at all
+    Transfers(AirportCode, AirportCode, Option<usize>),
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoHub(a) => write!(f, "`{a}` has no flight to a hub"),
+            Self::Transfers(a, b, Some(n)) => write!(
+                f,
+                "travelling between `{a}` and `{b}` needs {n} transfer{}",
+                if *n == 1 { "" } else { "s" }
+            ),
+            Self::Transfers(a, b, None) => write!(f, "`{a}` cannot be reached from `{b}`"),
+        }
+    }
+}
+
+/// The airports in the first tier, which are the hubs if there are no tiers
+pub fn hubs(config: &mut Config) -> Result<BTreeSet<AirportCode>> {
+    let mut hubs = BTreeSet::new();
+    for a in config.airports()? {
+        if config.airport_tier(&a)? == 0 {
+            hubs.insert(a);
+        }
+    }
+    Ok(hubs)
+}
+
+/// The ways in which `flights` do not meet `connectivity`, in order
+pub fn violations(config: &mut Config, flights: &[Flight]) -> Result<Vec<Violation>> {
+    let connectivity = config.connectivity.clone();
+    let airports = config.airports()?;
+    let network = Network::new(&airports, flights);
+    let mut violations = vec![];
+    if connectivity.every_nonhub_connects_to_hub {
+        let hubs = hubs(config)?;
+        violations.extend(
+            airports
+                .iter()
+                .filter(|a| !hubs.contains(*a) && !hubs.iter().any(|h| network.linked(a, h)))
+                .map(|a| Violation::NoHub(a.clone())),
+        );
+    }
+    if let Some(max) = connectivity.max_transfers_between_any_two_airports {
+        for a in &airports {
+            let hops = network.hops(a);
+            for b in airports.iter().filter(|b| a < *b) {
+                let transfers = hops.get(b).map(|n| n - 1);
+                if transfers.is_none_or(|n| n > usize::from(max)) {
+                    violations.push(Violation::Transfers(a.clone(), b.clone(), transfers));
+                }
+            }
+        }
+    }
+    violations.sort();
+    Ok(violations)
+}
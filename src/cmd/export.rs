@@ -0,0 +1,237 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use itertools::Itertools;
+use log::warn;
+use serde_json::json;
+
+use crate::types::{
+    flight::Flight, flight_data::FlightData, flight_type::FlightType, AirportCode, FlightNumber,
+    GateCode,
+};
+
+/// The colours of the flight types, in the order of their names
+const COLOURS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// The formats the plan can be exported in
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// A Graphviz graph
+    Dot,
+    #[value(name = "graphml")]
+    GraphMl,
+    /// Lines between the coordinates of the airports in Gatelogue. The positions are flat
+    /// block coordinates `[x, -z]` with north up, not WGS84 longitudes and latitudes
+    #[value(name = "geojson")]
+    GeoJson,
+}
+
+/// A leg of a flight, with the flight in the other direction merged in
+struct Edge<'a> {
+    number: FlightNumber,
+    from: &'a (AirportCode, GateCode),
+    to: &'a (AirportCode, GateCode),
+    flight: &'a Flight,
+}
+
+impl Edge<'_> {
+    fn label(&self) -> String {
+        format!("{} ({})", self.number, self.flight.ty)
+    }
+    /// Flights that other airlines also fly are dashed
+    const fn style(&self) -> &'static str {
+        if self.flight.ty.existing {
+            "dashed"
+        } else {
+            "solid"
+        }
+    }
+}
+
+/// The legs of `plan`, each once whichever way it is flown
+fn edges(plan: &[Flight]) -> Vec<Edge<'_>> {
+    let mut seen = BTreeSet::new();
+    let mut edges = vec![];
+    for f in plan.iter().sorted_by_key(|f| f.number) {
+        // the flight back the other way has the same stops in reverse
+        let stops = f.stops().sorted().collect::<Vec<_>>();
+        if !seen.insert(stops) {
+            continue;
+        }
+        edges.extend(f.legs().map(|(from, to)| Edge {
+            number: f.number,
+            from,
+            to,
+            flight: f,
+        }));
+    }
+    edges
+}
+
+/// The colour of each flight type's pair of tiers, regardless of whether it is existing
+fn colours(plan: &[Flight]) -> BTreeMap<String, &'static str> {
+    plan.iter()
+        .map(|f| f.ty.pair_name())
+        .unique()
+        .sorted()
+        .zip(COLOURS.iter().copied().cycle())
+        .collect()
+}
+
+fn colour<'a>(colours: &BTreeMap<String, &'a str>, ty: &FlightType) -> &'a str {
+    colours.get(&ty.pair_name()).copied().unwrap_or(COLOURS[0])
+}
+
+fn airports(plan: &[Flight]) -> BTreeSet<&AirportCode> {
+    plan.iter()
+        .flat_map(|f| f.stops().map(|(a, _)| a))
+        .collect()
+}
+
+/// Escapes `s` for XML text and attributes
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn dot(plan: &[Flight]) -> String {
+    let colours = colours(plan);
+    let mut lines = vec!["graph plan {".to_owned()];
+    lines.extend(airports(plan).into_iter().map(|a| format!("  {a:?};")));
+    lines.extend(edges(plan).iter().map(|e| {
+        format!(
+            "  {:?} -- {:?} [label={:?}, color={:?}, style={}];",
+            e.from.0.as_str(),
+            e.to.0.as_str(),
+            e.label(),
+            colour(&colours, &e.flight.ty),
+            e.style()
+        )
+    }));
+    lines.push("}".to_owned());
+    lines.join("\n") + "\n"
+}
+
+fn graphml(plan: &[Flight]) -> String {
+    let colours = colours(plan);
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#.to_owned(),
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#.to_owned(),
+    ];
+    for key in [
+        "number",
+        "type",
+        "color",
+        "style",
+        "from_gate",
+        "to_gate",
+        "size",
+    ] {
+        lines.push(format!(
+            r#"  <key id="{key}" for="edge" attr.name="{key}" attr.type="string"/>"#
+        ));
+    }
+    lines.push(r#"  <graph id="plan" edgedefault="undirected">"#.to_owned());
+    lines.extend(
+        airports(plan)
+            .into_iter()
+            .map(|a| format!(r#"    <node id="{}"/>"#, xml_escape(a))),
+    );
+    for e in edges(plan) {
+        lines.push(format!(
+            r#"    <edge source="{}" target="{}">"#,
+            xml_escape(&e.from.0),
+            xml_escape(&e.to.0)
+        ));
+        for (key, value) in [
+            ("number", e.number.to_string()),
+            ("type", e.flight.ty.to_string()),
+            ("color", colour(&colours, &e.flight.ty).to_owned()),
+            ("style", e.style().to_owned()),
+            ("from_gate", e.from.1.to_string()),
+            ("to_gate", e.to.1.to_string()),
+            ("size", e.flight.size.to_string()),
+        ] {
+            lines.push(format!(
+                r#"      <data key="{key}">{}</data>"#,
+                xml_escape(&value)
+            ));
+        }
+        lines.push("    </edge>".to_owned());
+    }
+    lines.push("  </graph>".to_owned());
+    lines.push("</graphml>".to_owned());
+    lines.join("\n") + "\n"
+}
+
+/// The position of a point at block coordinates `x` and `z`.
+/// North is towards -z in Minecraft, so `z` is flipped for north to be up on a map
+/// (subtracted from 0, as negating 0 would write it as -0.0)
+fn position((x, z): (f64, f64)) -> [f64; 2] {
+    [x, 0.0 - z]
+}
+
+/// The plan as `GeoJSON`, at the block coordinates of the airports in Gatelogue.
+/// Airports without coordinates are left out, with the legs to them
+fn geojson(plan: &[Flight], fd: &FlightData) -> Result<String> {
+    let colours = colours(plan);
+    let mut features = vec![];
+    let mut missing = BTreeSet::new();
+    for a in airports(plan) {
+        if let Some(coordinates) = fd.coordinates.get(a) {
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": position(*coordinates)},
+                "properties": {"airport": a},
+            }));
+        } else {
+            missing.insert(a);
+        }
+    }
+    for e in edges(plan) {
+        let (Some(from), Some(to)) = (fd.coordinates.get(&e.from.0), fd.coordinates.get(&e.to.0))
+        else {
+            continue;
+        };
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {"type": "LineString", "coordinates": [position(*from), position(*to)]},
+            "properties": {
+                "number": e.number,
+                "from": format!("{} {}", e.from.0, e.from.1),
+                "to": format!("{} {}", e.to.0, e.to.1),
+                "type": e.flight.ty.to_string(),
+                "size": e.flight.size,
+                "color": colour(&colours, &e.flight.ty),
+                "style": e.style(),
+            },
+        }));
+    }
+    if !missing.is_empty() {
+        warn!(
+            "These airports have no coordinates in Gatelogue, so they and their flights are left out: {}",
+            missing.iter().join(", ")
+        );
+    }
+    Ok(
+        serde_json::to_string_pretty(&json!({"type": "FeatureCollection", "features": features}))?
+            + "\n",
+    )
+}
+
+/// `plan` in `format`. `GeoJSON` needs the coordinates in `fd`
+pub fn export(plan: &[Flight], fd: Option<&FlightData>, format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Dot => Ok(dot(plan)),
+        ExportFormat::GraphMl => Ok(graphml(plan)),
+        ExportFormat::GeoJson => geojson(
+            plan,
+            fd.ok_or_else(|| anyhow!("GeoJSON needs the coordinates from Gatelogue"))?,
+        ),
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    cmd::update,
+    types::{
+        flight::Flight,
+        flight_data::{FlightData, FlightDataFlight},
+        AirportCode,
+    },
+};
+
+/// The flight data at one time, and the plan made with it if the snapshot was taken by `run`
+pub struct Snapshot {
+    pub fd: FlightData,
+    pub plan: Option<Vec<Flight>>,
+}
+
+/// Saves `fd` to `dir` as `<timestamp>.json`, and `plan` (the output of `run`) as `<timestamp>.txt`
+pub fn save(dir: &Path, fd: &FlightData, plan: Option<&str>) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let file = dir.join(format!("{}.json", fd.timestamp));
+    std::fs::write(&file, serde_json::to_string(fd)?)?;
+    if let Some(plan) = plan {
+        std::fs::write(file.with_extension("txt"), plan)?;
+    }
+    info!("Saved snapshot {}", file.display());
+    Ok(file)
+}
+
+/// The snapshots in `dir`, oldest first
+pub fn load(dir: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots = vec![];
+    let entries = if dir.exists() {
+        std::fs::read_dir(dir)?.collect()
+    } else {
+        vec![]
+    };
+    for entry in entries {
+        let file = entry?.path();
+        if file.extension().is_none_or(|e| e != "json") {
+            continue;
+        }
+        let fd: FlightData = serde_json::from_str(&std::fs::read_to_string(&file)?)
+            .map_err(|e| anyhow!("Invalid snapshot {}: {e}", file.display()))?;
+        let plan_file = file.with_extension("txt");
+        let plan = if plan_file.exists() {
+            Some(update::load_from_out(plan_file)?)
+        } else {
+            None
+        };
+        snapshots.push(Snapshot { fd, plan });
+    }
+    if snapshots.is_empty() {
+        return Err(anyhow!(
+            "There are no snapshots in {} (set `history_dir` and use `run`, or use `history --snapshot`)",
+            dir.display()
+        ));
+    }
+    snapshots.sort_by_key(|s| s.fd.timestamp);
+    Ok(snapshots)
+}
+
+/// How the flight data changed between two snapshots
+pub struct Period {
+    pub from: u64,
+    pub to: u64,
+    /// The number of other airlines' flights added at each airport
+    pub added: BTreeMap<AirportCode, usize>,
+    /// The number of other airlines' flights removed at each airport
+    pub removed: BTreeMap<AirportCode, usize>,
+    /// Airports that are in the later snapshot but not the earlier one
+    pub new_airports: Vec<AirportCode>,
+}
+
+/// How the flight data and plans changed over the snapshots
+pub struct Trends {
+    pub periods: Vec<Period>,
+    /// The percentage of the flights of each saved plan that other airlines also flew then,
+    /// by the time of its snapshot
+    pub duplication: Vec<(u64, f64)>,
+}
+
+/// The number of flights in `a` but not `b` at each airport
+fn flights_not_in(a: &FlightData, b: &FlightData) -> BTreeMap<AirportCode, usize> {
+    let key = |f: &FlightDataFlight| {
+        (
+            f.airline.clone(),
+            f.flight_number.clone(),
+            f.airports.clone(),
+        )
+    };
+    let b = b.flights.iter().map(key).collect::<BTreeSet<_>>();
+    a.flights
+        .iter()
+        .filter(|f| !b.contains(&key(f)))
+        .flat_map(|f| f.airports.iter().unique().cloned())
+        .counts()
+        .into_iter()
+        .collect()
+}
+
+/// The trends over `snapshots`, which are oldest first
+pub fn trends(snapshots: &[Snapshot]) -> Trends {
+    let airports = |fd: &FlightData| {
+        fd.old_world_airports
+            .iter()
+            .chain(&fd.new_world_airports)
+            .cloned()
+            .collect::<BTreeSet<_>>()
+    };
+    let periods = snapshots
+        .iter()
+        .tuple_windows()
+        .map(|(old, new)| Period {
+            from: old.fd.timestamp,
+            to: new.fd.timestamp,
+            added: flights_not_in(&new.fd, &old.fd),
+            removed: flights_not_in(&old.fd, &new.fd),
+            new_airports: airports(&new.fd)
+                .difference(&airports(&old.fd))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+    let duplication = snapshots
+        .iter()
+        .filter_map(|s| {
+            let plan = s.plan.as_ref().filter(|p| !p.is_empty())?;
+            let duplicates = plan.iter().filter(|f| f.ty.existing).count();
+            Some((
+                s.fd.timestamp,
+                duplicates as f64 / plan.len() as f64 * 100.0,
+            ))
+        })
+        .collect();
+    Trends {
+        periods,
+        duplication,
+    }
+}
+
+/// `timestamp` (in seconds since the Unix epoch) as a UTC date and time
+fn date(timestamp: u64) -> String {
+    // the days since 0000-03-01, so that leap days are at the end of each year
+    let days = timestamp / 86400 + 719_468;
+    let (era, day_of_era) = (days / 146_097, days % 146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    let seconds = timestamp % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+impl Display for Trends {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |map: &BTreeMap<AirportCode, usize>| {
+            map.iter().map(|(a, n)| format!("{a} {n}")).join(", ")
+        };
+        writeln!(f, "==Competitor Flights==")?;
+        for p in &self.periods {
+            writeln!(f, "{} to {}:", date(p.from), date(p.to))?;
+            if p.added.is_empty() && p.removed.is_empty() && p.new_airports.is_empty() {
+                writeln!(f, "  No changes")?;
+                continue;
+            }
+            if !p.added.is_empty() {
+                writeln!(f, "  Flights added: {}", counts(&p.added))?;
+            }
+            if !p.removed.is_empty() {
+                writeln!(f, "  Flights removed: {}", counts(&p.removed))?;
+            }
+            if !p.new_airports.is_empty() {
+                writeln!(f, "  New airports: {}", p.new_airports.join(", "))?;
+            }
+        }
+
+        writeln!(f, "==Duplication==")?;
+        for (timestamp, percent) in &self.duplication {
+            writeln!(f, "{}: {percent:.2}% duplicates", date(*timestamp))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01 00:00 UTC");
+        assert_eq!(date(1_760_000_000), "2025-10-09 08:53 UTC");
+        assert_eq!(date(1_709_164_800), "2024-02-29 00:00 UTC");
+        assert_eq!(date(951_782_400), "2000-02-29 00:00 UTC");
+        // 2100 is not a leap year
+        assert_eq!(date(4_107_542_399), "2100-02-28 23:59 UTC");
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::types::{
+    config::Config, flight::Flight, flight_data::FlightData, flight_type::FlightType,
+    flight_utils::FlightUtils, gate::Gate, rule::Route, size, AirlineName, AirportCode, GateCode,
+};
+
+/// The number of replacement destinations suggested for each gate
+const SUGGESTIONS_PER_GATE: usize = 3;
+
+/// A flight of an old plan whose flight type is different with the current flight data
+pub struct Change {
+    pub flight: Flight,
+    pub new_ty: FlightType,
+    /// The airlines and flight numbers of the other airlines' flights on the route
+    pub competitors: Vec<(AirlineName, SmolStr)>,
+}
+
+/// Destinations that a gate with a flight that became a duplicate could fly to instead
+pub struct Suggestion {
+    pub gate: Gate,
+    /// Gates that no other airline flies to from the gate's airport, with the score of the flight
+    pub replacements: Vec<(Gate, i8)>,
+}
+
+/// How the flights of other airlines in Gatelogue affect an old plan
+pub struct Impact {
+    pub changes: Vec<Change>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// The gate with the airport and code of `stop`, if the airline still has it
+fn find_gate<'a>(gates: &'a [Gate], stop: &(AirportCode, GateCode)) -> Option<&'a Gate> {
+    gates
+        .iter()
+        .find(|g| (&g.airport, &g.code) == (&stop.0, &stop.1))
+}
+
+/// Re-classifies the flights of `plan` against `fd`, and suggests replacements for the gates
+/// whose flights became duplicates
+pub fn impact(config: &mut Config, fd: &FlightData, plan: &[Flight]) -> Result<Impact> {
+    let gates = config.gates()?;
+    let mut changes = vec![];
+    for flight in plan.iter().cloned() {
+        // the type of a multi-stop flight is given by its first and last stops
+        let new_ty = match (
+            find_gate(&gates, &flight.airport1),
+            find_gate(&gates, &flight.airport2),
+        ) {
+            (Some(g1), Some(g2)) => (g1, g2).get_flight_type(config, fd)?,
+            _ => (&flight.airport1.0, &flight.airport2.0).get_flight_type(config, fd)?,
+        };
+        if new_ty == flight.ty {
+            continue;
+        }
+        let competitors = fd
+            .flights
+            .iter()
+            .filter(|f| {
+                f.airports.contains(&flight.airport1.0) && f.airports.contains(&flight.airport2.0)
+            })
+            .map(|f| (f.airline.clone(), f.flight_number.clone()))
+            .collect();
+        changes.push(Change {
+            flight,
+            new_ty,
+            competitors,
+        });
+    }
+
+    let dests = |g: &Gate| {
+        plan.iter()
+            .flat_map(Flight::legs)
+            .filter(|(from, _)| (&from.0, &from.1) == (&g.airport, &g.code))
+            .map(|(_, (a, _))| a.clone())
+            .collect::<Vec<_>>()
+    };
+    let mut suggestions = vec![];
+    let affected = changes
+        .iter()
+        .filter(|c| c.new_ty.existing && !c.flight.ty.existing)
+        .flat_map(|c| [&c.flight.airport1, &c.flight.airport2])
+        .filter_map(|stop| find_gate(&gates, stop))
+        .unique()
+        .cloned()
+        .collect::<Vec<_>>();
+    for gate in affected {
+        let served = dests(&gate);
+        let mut replacements = vec![];
+        for other in &gates {
+            if other.airport == gate.airport
+                || served.contains(&other.airport)
+                || fd.num_flights(&gate.airport, &other.airport) > 0
+                || size::plane_size(&config.sizes, &gate, other).is_none()
+                || dests(other).len() >= config.hard_max(other)?
+            {
+                continue;
+            }
+            let route = Route::between_gates(config, fd, &gate, other)?;
+            if config.judge(&route).forbidden_by.is_some() {
+                continue;
+            }
+            replacements.push((other.clone(), (&gate, other).score(config, fd)?));
+        }
+        let replacements = replacements
+            .into_iter()
+            .sorted_by_key(|(g, s)| (std::cmp::Reverse(*s), g.clone()))
+            .unique_by(|(g, _)| g.airport.clone())
+            .take(SUGGESTIONS_PER_GATE)
+            .collect();
+        suggestions.push(Suggestion { gate, replacements });
+    }
+
+    Ok(Impact {
+        changes,
+        suggestions,
+    })
+}
+
+impl Display for Impact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "==Changed Flights==")?;
+        for c in &self.changes {
+            let fl = &c.flight;
+            write!(
+                f,
+                "{} {} {} -> {} {}: {} -> {}",
+                fl.number,
+                fl.airport1.0,
+                fl.airport1.1,
+                fl.airport2.0,
+                fl.airport2.1,
+                fl.ty,
+                c.new_ty
+            )?;
+            if c.competitors.is_empty() {
+                writeln!(f)?;
+            } else {
+                writeln!(
+                    f,
+                    " (flown by {})",
+                    c.competitors
+                        .iter()
+                        .map(|(airline, number)| format!("{airline} {number}"))
+                        .join(", ")
+                )?;
+            }
+        }
+
+        writeln!(f, "==Suggested Replacements==")?;
+        for s in &self.suggestions {
+            if s.replacements.is_empty() {
+                writeln!(f, "{}: no unique destinations left", s.gate)?;
+            } else {
+                writeln!(
+                    f,
+                    "{}: {}",
+                    s.gate,
+                    s.replacements
+                        .iter()
+                        .map(|(g, score)| format!("{} {} (score {score})", g.airport, g.code))
+                        .join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
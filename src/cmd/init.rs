@@ -0,0 +1,191 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use counter::Counter;
+use dialoguer::{Input, MultiSelect};
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    cmd::validate,
+    types::{config::Config, AirlineName, AirportCode, FlightNumber},
+};
+
+/// The answers to the questions of [`init`], given as flags
+pub struct InitOptions {
+    pub airline_name: Option<AirlineName>,
+    pub gate_file: Option<PathBuf>,
+    pub hubs: Option<Vec<AirportCode>>,
+    pub hub_threshold: Option<usize>,
+    /// Whether to ask for the answers that are not given, instead of using the defaults
+    pub interactive: bool,
+}
+
+/// Flight number ranges are allocated in blocks of this size
+const BLOCK_SIZE: usize = 100;
+
+/// Makes a starter config to be written to `output`,
+/// asking for anything not given in `options` if interactive
+pub fn init(output: &Path, options: InitOptions) -> Result<Config> {
+    let mut config = Config::default();
+    config._folder = output.parent().map(ToOwned::to_owned);
+
+    config.airline_name = match options.airline_name {
+        Some(name) => name,
+        None if options.interactive => Input::<String>::new()
+            .with_prompt("Airline name")
+            .interact_text()?
+            .into(),
+        None => return Err(anyhow!("--airline-name is required when not interactive")),
+    };
+
+    let folder = config._folder.clone().unwrap_or_default();
+    config.gate_file = Some(match options.gate_file {
+        Some(gate_file) => gate_file,
+        None if options.interactive => Input::<String>::new()
+            .with_prompt(format!(
+                "Gate file, with one `<airport> <gate_no> <size>` row for each gate or in CSV, TSV, YAML or JSON (relative to {})",
+                folder.display()
+            ))
+            .default("gates.txt".into())
+            .validate_with(|f: &String| {
+                if folder.join(f).is_file() {
+                    Ok(())
+                } else {
+                    Err("File not found")
+                }
+            })
+            .interact_text()?
+            .into(),
+        None => return Err(anyhow!("--gate-file is required when not interactive")),
+    });
+    let gates = config.gates()?;
+    if gates.is_empty() {
+        return Err(anyhow!("No gates found in the gate file"));
+    }
+
+    let hub_threshold = options.hub_threshold.unwrap_or(5);
+    config.set_hubs(vec![], hub_threshold);
+    let suggested = config.hubs()?;
+    let hubs = match options.hubs {
+        Some(hubs) => hubs,
+        None if options.interactive => {
+            let gate_counts = gates
+                .iter()
+                .map(|g| g.airport.clone())
+                .collect::<Counter<_>>();
+            let airports = gate_counts
+                .most_common_tiebreaker(Ord::cmp)
+                .into_iter()
+                .collect::<Vec<_>>();
+            let chosen = MultiSelect::new()
+                .with_prompt(format!(
+                    "Hubs (airports with at least {hub_threshold} gates are selected)"
+                ))
+                .items(airports.iter().map(|(a, n)| format!("{a} ({n} gates)")))
+                .defaults(
+                    &airports
+                        .iter()
+                        .map(|(a, _)| suggested.contains(a))
+                        .collect::<Vec<_>>(),
+                )
+                .interact()?;
+            chosen
+                .into_iter()
+                .map(|i| airports[i].0.clone())
+                .sorted()
+                .collect()
+        }
+        None => suggested.clone(),
+    };
+    // hubs are only listed if they differ from the ones found with `hub_threshold`
+    if hubs != suggested {
+        config.set_hubs(hubs.clone(), hub_threshold);
+    }
+    info!("Hubs: {}", hubs.join(", "));
+
+    allocate_ranges(&mut config, &hubs)?;
+    Ok(config)
+}
+
+/// Allocates non-overlapping flight number ranges to hub-to-hub, hub-to-non-hub and
+/// non-hub-to-non-hub flights, in that order, with room for half as many flights again
+/// as could be planned
+fn allocate_ranges(config: &mut Config, hubs: &[AirportCode]) -> Result<()> {
+    for hub in hubs {
+        config.range_h2n.insert(hub.clone(), vec![]);
+    }
+    let gates = config.gates()?;
+    let needed = validate::needed_numbers(config, None, &gates)?;
+    let needed = |name: &str, airport: Option<&AirportCode>| {
+        needed
+            .get(&(name.to_owned(), airport.cloned()))
+            .copied()
+            .unwrap_or_default()
+    };
+
+    let mut next = 1usize;
+    let mut allocate = |n: usize| -> Result<Vec<(FlightNumber, FlightNumber)>> {
+        let size = (n + n / 2).div_ceil(BLOCK_SIZE).max(1) * BLOCK_SIZE;
+        let range = (
+            FlightNumber::try_from(next)?,
+            FlightNumber::try_from(next + size - 1)?,
+        );
+        next += size;
+        Ok(vec![range])
+    };
+    config.range_h2h = allocate(needed("H2H", None))?;
+    for hub in hubs {
+        let range = allocate(needed("H2N", Some(hub)))?;
+        config.range_h2n.insert(hub.clone(), range);
+    }
+    config.range_n2n = allocate(needed("N2N", None))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::gate::PartialGate;
+
+    fn with_gates(airports: &[&str]) -> Config {
+        let mut config = Config::default();
+        for airport in airports {
+            let gate: PartialGate = serde_yaml::from_str("{code: '1', size: S}").unwrap();
+            config.gates.insert((*airport).into(), vec![gate]);
+        }
+        config
+    }
+
+    fn ranges(ranges: &[(FlightNumber, FlightNumber)]) -> Vec<(usize, usize)> {
+        ranges
+            .iter()
+            .map(|(a, b)| (usize::from(*a), usize::from(*b)))
+            .collect()
+    }
+
+    #[test]
+    fn allocates_blocks_in_order() {
+        let mut config = with_gates(&["AAA", "BBB", "HHH", "JJJ"]);
+        let hubs = vec!["HHH".into(), "JJJ".into()];
+        config.set_hubs(hubs.clone(), 5);
+        allocate_ranges(&mut config, &hubs).unwrap();
+        assert_eq!(ranges(&config.range_h2h), [(1, 100)]);
+        assert_eq!(ranges(&config.range_h2n["HHH"]), [(101, 200)]);
+        assert_eq!(ranges(&config.range_h2n["JJJ"]), [(201, 300)]);
+        assert_eq!(ranges(&config.range_n2n), [(301, 400)]);
+    }
+
+    #[test]
+    fn leaves_room_for_half_as_many_again() {
+        // 12 airports have 66 pairs, which fit in a block with room for 33 more, but 13 have 78
+        let airports = (0..13).map(|i| format!("A{i:02}")).collect::<Vec<_>>();
+        let airports = airports.iter().map(String::as_str).collect::<Vec<_>>();
+        let mut config = with_gates(&airports[..12]);
+        allocate_ranges(&mut config, &[]).unwrap();
+        assert_eq!(ranges(&config.range_n2n), [(101, 200)]);
+        let mut config = with_gates(&airports);
+        allocate_ranges(&mut config, &[]).unwrap();
+        assert_eq!(ranges(&config.range_n2n), [(101, 300)]);
+    }
+}
@@ -0,0 +1,337 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use schemars::Schema;
+use serde_yaml::{Mapping, Value};
+
+use crate::types::{
+    config::{Config, CONFIG_VERSION},
+    config_error::ConfigError,
+    config_merge,
+};
+
+/// A change to the config format, which upgrades a config from the version before it
+struct Migration {
+    /// The version of the config format after this change
+    version: u8,
+    /// Upgrades the settings, returning a description of each change made
+    apply: fn(&mut Mapping, &Mapping) -> Vec<String>,
+}
+
+/// The changes to the config format, in order of version
+const MIGRATIONS: [Migration; 1] = [Migration {
+    version: 2,
+    apply: replace_empty,
+}];
+
+/// The result of [`migrate`]
+pub struct Migrated {
+    /// The upgraded config file
+    pub source: String,
+    /// A description of each change made, empty if the file is already up to date
+    pub changes: Vec<String>,
+}
+
+/// Upgrades the config file `file` to the current version of the config format and adds
+/// the settings it does not give with their defaults.
+/// Settings that are not changed are kept as they are written, with their comments
+pub fn migrate(file: &Path) -> Result<Migrated> {
+    let source = std::fs::read_to_string(file)?;
+    let Value::Mapping(original) =
+        serde_yaml::from_str(&source).map_err(|e| ConfigError::new(file, &source, &e))?
+    else {
+        return Err(anyhow!("{} is not a mapping of settings", file.display()));
+    };
+    let version = match original.get("config_version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u8::try_from(v).ok())
+            .ok_or_else(|| anyhow!("config_version must be a whole number"))?,
+    };
+    if version > CONFIG_VERSION {
+        return Err(anyhow!(
+            "{} is in version {version} of the config format, which is newer than this version of flight-network-planner supports ({CONFIG_VERSION})",
+            file.display()
+        ));
+    }
+
+    let defaults = match serde_yaml::to_value(Config::default())? {
+        Value::Mapping(defaults) => defaults,
+        _ => Mapping::new(),
+    };
+    let mut settings = original.clone();
+    let mut changes = vec![];
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        changes.extend((migration.apply)(&mut settings, &defaults));
+    }
+    if version < CONFIG_VERSION {
+        settings.insert("config_version".into(), CONFIG_VERSION.into());
+        changes.push(format!(
+            "`config_version` set to {CONFIG_VERSION} (from {version})"
+        ));
+    }
+    // settings not given in a config that extends others are taken from them instead
+    if config_merge::extends(&Value::Mapping(settings.clone()))?.is_empty() {
+        for (key, default) in &defaults {
+            if !settings.contains_key(key) {
+                settings.insert(key.clone(), default.clone());
+                changes.push(format!(
+                    "`{}` added with its default value",
+                    key.as_str().unwrap_or_default()
+                ));
+            }
+        }
+        serde_yaml::from_value::<Config>(Value::Mapping(settings.clone()))
+            .map_err(|e| ConfigError::new(file, "", &e))?;
+    }
+
+    Ok(Migrated {
+        source: render(&source, &original, &settings)?,
+        changes,
+    })
+}
+
+/// Migration to version 2: settings left empty (`key:` with no value) are given their defaults,
+/// as the defaults of most settings are no longer empty
+fn replace_empty(settings: &mut Mapping, defaults: &Mapping) -> Vec<String> {
+    let mut changes = vec![];
+    for (key, value) in settings.iter_mut() {
+        if let Some(default) = defaults
+            .get(key)
+            .filter(|d| value.is_null() && !d.is_null())
+        {
+            value.clone_from(default);
+            changes.push(format!(
+                "`{}` was empty, so it is now its default value",
+                key.as_str().unwrap_or_default()
+            ));
+        }
+    }
+    changes
+}
+
+/// A part of a config file
+enum Part<'a> {
+    /// Lines that are not part of a setting, such as comments separated from settings by blank lines
+    Text(Vec<&'a str>),
+    /// A top-level setting
+    Setting {
+        key: &'a str,
+        /// The comments directly above the setting
+        comments: Vec<&'a str>,
+        lines: Vec<&'a str>,
+    },
+}
+
+/// The key of the top-level setting that starts on `line`, if any
+fn top_level_key(line: &str) -> Option<&str> {
+    let (key, rest) = line.split_once(':')?;
+    (!key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+')
+        && (rest.is_empty() || rest.starts_with(char::is_whitespace)))
+    .then_some(key)
+}
+
+/// Splits a config file into its top-level settings and the text between them
+fn parts(source: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut pending = vec![];
+    for line in source.lines() {
+        if let Some(key) = top_level_key(line) {
+            // comments separated from the setting by a blank line are not about it
+            let start = pending
+                .iter()
+                .rposition(|l: &&str| l.trim().is_empty())
+                .map_or(0, |i| i + 1);
+            let comments = pending.split_off(start);
+            if !pending.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut pending)));
+            }
+            parts.push(Part::Setting {
+                key,
+                comments,
+                lines: vec![line],
+            });
+        } else if let (Some(Part::Setting { lines, .. }), true) = (
+            parts.last_mut(),
+            line.starts_with(char::is_whitespace) || line.starts_with('-'),
+        ) {
+            if !line.trim().is_empty() {
+                lines.append(&mut pending);
+            }
+            lines.push(line);
+        } else {
+            pending.push(line);
+        }
+    }
+    if !pending.is_empty() {
+        parts.push(Part::Text(pending));
+    }
+    parts
+}
+
+/// A setting as YAML, preceded by `comments`
+fn setting(comments: &[&str], key: &Value, value: &Value) -> Result<String> {
+    let mut mapping = Mapping::new();
+    mapping.insert(key.clone(), value.clone());
+    let yaml = serde_yaml::to_string(&mapping)?;
+    Ok(comments
+        .iter()
+        .map(|c| format!("{c}\n"))
+        .chain([yaml])
+        .collect())
+}
+
+/// A setting that is not in the config file yet, preceded by its description from the JSON Schema
+fn new_setting(schema: &Schema, settings: &Mapping, key: &Value) -> Result<String> {
+    let Some(value) = settings.get(key) else {
+        return Ok(String::new());
+    };
+    let name = key.as_str().unwrap_or_default();
+    let comments = schema
+        .get("properties")
+        .and_then(|p| p.get(name))
+        .and_then(|p| p.get("description"))
+        .and_then(|d| d.as_str())
+        .map(|d| {
+            d.lines()
+                .enumerate()
+                .map(|(i, l)| {
+                    if i == 0 {
+                        format!("# {name}: {l}")
+                    } else {
+                        format!("# {}{l}", " ".repeat(name.len() + 2))
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    setting(
+        &comments.iter().map(String::as_str).collect::<Vec<_>>(),
+        key,
+        value,
+    )
+}
+
+/// Writes `settings` in the layout of `source`, whose settings are `original`.
+/// Settings that are unchanged are copied from `source`, changed ones are rewritten
+/// below their comments, and new ones are added at the end, apart from `config_version`,
+/// which is added before the first setting
+fn render(source: &str, original: &Mapping, settings: &Mapping) -> Result<String> {
+    let schema = schemars::schema_for!(Config);
+    let version_key = Value::from("config_version");
+    let parts = parts(source);
+    let mut written = parts
+        .iter()
+        .filter_map(|p| match p {
+            Part::Setting { key, .. } => Some(Value::from(*key)),
+            Part::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let mut output = String::new();
+    for part in &parts {
+        match part {
+            Part::Text(lines) => {
+                for line in lines {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+            }
+            Part::Setting {
+                key,
+                comments,
+                lines,
+            } => {
+                if !written.contains(&version_key) {
+                    output.push_str(&new_setting(&schema, settings, &version_key)?);
+                    output.push('\n');
+                    written.push(version_key.clone());
+                }
+                let key = Value::from(*key);
+                let Some(value) = settings.get(&key) else {
+                    continue;
+                };
+                if original.get(&key) == Some(value) {
+                    for line in comments.iter().chain(lines) {
+                        output.push_str(line);
+                        output.push('\n');
+                    }
+                } else {
+                    output.push_str(&setting(comments, &key, value)?);
+                }
+            }
+        }
+    }
+
+    let new = settings
+        .keys()
+        .filter(|k| !written.contains(k))
+        .collect::<Vec<_>>();
+    if !new.is_empty() {
+        if !output.is_empty() && !output.ends_with("\n\n") {
+            output.push('\n');
+        }
+        output.push_str("# == Added by migrate-config ==\n");
+        for key in new {
+            output.push_str(&new_setting(&schema, settings, key)?);
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "# My airline
+
+# the name
+airline_name: Foo  # inline
+# hubs
+hubs:
+  # first
+  - AAA
+# threshold
+hub_threshold:
+";
+
+    fn render_with(changes: &[(&str, Value)]) -> String {
+        let Value::Mapping(original) = serde_yaml::from_str(SOURCE).unwrap() else {
+            unreachable!()
+        };
+        let mut settings = original.clone();
+        for (key, value) in changes {
+            settings.insert((*key).into(), value.clone());
+        }
+        render(SOURCE, &original, &settings).unwrap()
+    }
+
+    #[test]
+    fn unchanged_settings_keep_their_comments() {
+        let output = render_with(&[("config_version", 2.into())]);
+        let (header, rest) = output.split_once("# config_version: ").unwrap();
+        assert_eq!(header, "# My airline\n\n");
+        assert!(rest.contains("\nconfig_version: 2\n\n# the name\nairline_name: Foo  # inline\n"));
+        assert!(rest.ends_with("# hubs\nhubs:\n  # first\n  - AAA\n# threshold\nhub_threshold:\n"));
+    }
+
+    #[test]
+    fn changed_settings_are_rewritten_below_their_comments() {
+        let output = render_with(&[
+            ("config_version", 2.into()),
+            ("hub_threshold", 5.into()),
+            ("max_h2h", 6.into()),
+        ]);
+        assert!(
+            output.contains("# hubs\nhubs:\n  # first\n  - AAA\n# threshold\nhub_threshold: 5\n")
+        );
+        let (_, added) = output
+            .split_once("\n\n# == Added by migrate-config ==\n")
+            .unwrap();
+        assert!(added.starts_with("# max_h2h: "));
+        assert!(added.ends_with("\nmax_h2h: 6\n"));
+    }
+}
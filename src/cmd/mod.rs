@@ -1,3 +1,16 @@
+pub mod analyze;
+pub mod connectivity;
+pub mod export;
+pub mod history;
+pub mod impact;
+pub mod init;
+pub mod migrate;
+pub mod multi_stop;
+pub mod route;
 pub mod run;
+pub mod schema;
 pub mod stats;
 pub mod update;
+pub mod validate;
+pub mod what_if;
+pub mod wiki;
@@ -0,0 +1,379 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use anyhow::{anyhow, Result};
+use counter::Counter;
+use itertools::Itertools;
+use log::{debug, info};
+
+use crate::{
+    types::{
+        config::Config, flight::Flight, flight_type::FlightType, flight_utils::FlightUtils,
+        fng::FlightNumberGenerator, gate::Gate, size, AirportCode,
+    },
+    FlightData,
+};
+
+struct Planner<'a> {
+    config: &'a mut Config,
+    fd: &'a FlightData,
+    legs: HashMap<Gate, Vec<Gate>>,
+    destinations: &'a mut HashMap<Gate, Vec<(AirportCode, FlightType)>>,
+    served: HashSet<(AirportCode, AirportCode)>,
+    pair_scores: HashMap<(AirportCode, AirportCode), i8>,
+    endpoint_counts: Counter<Gate>,
+    max_per_gate: usize,
+}
+
+impl Planner<'_> {
+    fn pair(a: &AirportCode, b: &AirportCode) -> (AirportCode, AirportCode) {
+        if a < b {
+            (a.clone(), b.clone())
+        } else {
+            (b.clone(), a.clone())
+        }
+    }
+    fn pair_score(&mut self, a: &AirportCode, b: &AirportCode) -> Result<i8> {
+        let pair = Self::pair(a, b);
+        if let Some(s) = self.pair_scores.get(&pair) {
+            return Ok(*s);
+        }
+        let s = (&pair.0, &pair.1).score(self.config, self.fd)?;
+        self.pair_scores.insert(pair, s);
+        Ok(s)
+    }
+    /// Whether the gate can take on the gates in `new` as additional destinations
+    fn has_room(&mut self, g: &Gate, new: &[&Gate]) -> Result<bool> {
+        let mut dests = self.destinations.get(g).cloned().unwrap_or_default();
+        if dests.len() + new.len() > self.config.hard_max(g)? {
+            return Ok(false);
+        }
+        let mut new_types = vec![];
+        for og in new {
+            let ty = (g, *og).get_flight_type(self.config, self.fd)?;
+            dests.push((og.airport.clone(), ty.clone()));
+            new_types.push(ty);
+        }
+        for ty in new_types {
+            if dests.iter().filter(|(_, dty)| *dty == ty).count() > self.config.type_max(g, &ty)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+    /// The score of a route, crediting each pair of airports it serves that is not served yet.
+    /// Routes that do not serve any new non-adjacent pair are no better than direct flights
+    /// and are not scored
+    fn route_score(&mut self, route: &[Gate]) -> Result<Option<i16>> {
+        let mut s = 0i16;
+        let mut serves_new_connection = false;
+        for ((i, g1), (j, g2)) in route.iter().enumerate().tuple_combinations() {
+            // passengers can fly between any two stops, so every pair must be allowed
+            if self
+                .legs
+                .get(g1)
+                .is_none_or(|nexts| nexts.binary_search(g2).is_err())
+            {
+                return Ok(None);
+            }
+            if self.served.contains(&Self::pair(&g1.airport, &g2.airport)) {
+                continue;
+            }
+            let ps = self.pair_score(&g1.airport, &g2.airport)?;
+            if ps <= 0 {
+                continue;
+            }
+            s += i16::from(ps);
+            if j > i + 1 {
+                serves_new_connection = true;
+            }
+        }
+        Ok(serves_new_connection.then_some(s))
+    }
+    fn extend(
+        &mut self,
+        route: &mut Vec<Gate>,
+        max_stops: usize,
+        best: &mut Option<(i16, Vec<Gate>)>,
+    ) -> Result<()> {
+        let last = route.last().unwrap().to_owned();
+        let nexts = self.legs.get(&last).cloned().unwrap_or_default();
+        for next in nexts {
+            // the plane size is decided by the first leg, and every later stop must fit it
+            let plane =
+                size::plane_size(&self.config.sizes, &route[0], route.get(1).unwrap_or(&next));
+            if plane.is_none_or(|plane| !size::fits(&self.config.sizes, &plane, &next.size))
+                || route.iter().any(|g| g.airport == next.airport)
+                || self
+                    .served
+                    .contains(&Self::pair(&last.airport, &next.airport))
+            {
+                continue;
+            }
+            // the current last gate becomes either the origin with one destination,
+            // or an intermediate stop with two destinations
+            let room = if route.len() > 1 {
+                let prev = route[route.len() - 2].clone();
+                self.has_room(&last, &[&prev, &next])?
+            } else {
+                self.has_room(&last, &[&next])?
+            };
+            if !room {
+                continue;
+            }
+            route.push(next);
+            if route.len() >= 3 {
+                let end = route.last().unwrap().to_owned();
+                let prev = route[route.len() - 2].clone();
+                if self.endpoint_counts[&end] < self.max_per_gate
+                    && self.has_room(&end, &[&prev])?
+                {
+                    if let Some(s) = self.route_score(route)? {
+                        if best.as_ref().is_none_or(|(bs, br)| {
+                            (s, Reverse(route.as_slice())) > (*bs, Reverse(br.as_slice()))
+                        }) {
+                            *best = Some((s, route.clone()));
+                        }
+                    }
+                }
+            }
+            if route.len() < max_stops {
+                self.extend(route, max_stops, best)?;
+            }
+            route.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Plans multi-stop flights, before the remaining capacity is filled with direct flights.
+/// Each multi-stop flight must start or end at an airport with at most
+/// `multi_stop.small_airport_max_gates` gates, and every pair of stops must be a flight that
+/// would have been allowed on its own
+#[expect(clippy::too_many_lines)]
+pub fn plan(
+    config: &mut Config,
+    fd: &FlightData,
+    allowed_legs: &HashSet<(Gate, Gate)>,
+    destinations: &mut HashMap<Gate, Vec<(AirportCode, FlightType)>>,
+    flights: &mut Vec<Flight>,
+) -> Result<()> {
+    info!("Planning multi-stop flights");
+    let gates = config.gates()?;
+    let gate_counts = gates
+        .iter()
+        .map(|g| g.airport.clone())
+        .collect::<Counter<_>>();
+    let small_airport_max_gates = config.multi_stop.small_airport_max_gates;
+    let max_per_gate = config.multi_stop.max_per_gate as usize;
+    let max_stops = config.multi_stop.max_legs as usize + 1;
+    let both_dir_same_num = config.both_dir_same_num;
+    let mut fng = FlightNumberGenerator::new(config.multi_stop.range.clone());
+
+    let mut legs: HashMap<Gate, Vec<Gate>> = HashMap::new();
+    for (g1, g2) in allowed_legs {
+        legs.entry(g1.to_owned()).or_default().push(g2.to_owned());
+    }
+    for v in legs.values_mut() {
+        v.sort();
+    }
+    let served = flights
+        .iter()
+        .flat_map(|f| {
+            f.stops()
+                .tuple_combinations()
+                .map(|(a, b)| Planner::pair(&a.0, &b.0))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let mut planner = Planner {
+        config,
+        fd,
+        legs,
+        destinations,
+        served,
+        pair_scores: HashMap::new(),
+        endpoint_counts: Counter::new(),
+        max_per_gate,
+    };
+
+    loop {
+        let mut best = None;
+        for start in &gates {
+            if gate_counts[&start.airport] > small_airport_max_gates
+                || planner.endpoint_counts[start] >= max_per_gate
+            {
+                continue;
+            }
+            planner.extend(&mut vec![start.to_owned()], max_stops, &mut best)?;
+        }
+        let Some((s, route)) = best else {
+            break;
+        };
+        debug!(
+            "Accepted multi-stop route {} ({s})",
+            route.iter().map(ToString::to_string).join(" -> ")
+        );
+
+        let (first, last) = (&route[0], &route[route.len() - 1]);
+        let ty = (first, last).get_flight_type(planner.config, planner.fd)?;
+        let fn1 = fng.find(|a| !flights.iter().map(|f| f.number).contains(a));
+        let fn2 = if both_dir_same_num {
+            fn1
+        } else {
+            fng.find(|a| !flights.iter().map(|f| f.number).contains(a))
+        };
+        let (Some(fn1), Some(fn2)) = (fn1, fn2) else {
+            return Err(anyhow!(
+                "Could not generate flight number for multi-stop flight {} -> {}",
+                first.airport,
+                last.airport
+            ));
+        };
+
+        for (i, g) in route.iter().enumerate() {
+            for og in [i.checked_sub(1), Some(i + 1)]
+                .into_iter()
+                .filter_map(|j| route.get(j?))
+            {
+                let ty = (g, og).get_flight_type(planner.config, planner.fd)?;
+                planner
+                    .destinations
+                    .entry(g.to_owned())
+                    .or_default()
+                    .push((og.airport.clone(), ty));
+            }
+        }
+        planner.endpoint_counts[first] += 1;
+        planner.endpoint_counts[last] += 1;
+        for (a, b) in route.iter().tuple_combinations() {
+            planner.served.insert(Planner::pair(&a.airport, &b.airport));
+        }
+
+        let score = i8::try_from(s).unwrap_or(i8::MAX);
+        for (number, stops) in [
+            (fn1, route.iter().collect::<Vec<_>>()),
+            (fn2, route.iter().rev().collect::<Vec<_>>()),
+        ] {
+            let flight = Flight {
+                number,
+                airport1: (stops[0].airport.clone(), stops[0].code.clone()),
+                airport2: (
+                    stops[stops.len() - 1].airport.clone(),
+                    stops[stops.len() - 1].code.clone(),
+                ),
+                via: stops[1..stops.len() - 1]
+                    .iter()
+                    .map(|g| (g.airport.clone(), g.code.clone()))
+                    .collect(),
+                size: size::plane_size(&planner.config.sizes, first, &route[1])
+                    .unwrap_or_else(|| first.size.clone()),
+                score,
+                ty: ty.clone(),
+            };
+            info!("{flight}");
+            flights.push(flight);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn gate(airport: &str) -> Gate {
+        Gate {
+            airport: airport.into(),
+            code: "1".into(),
+            size: "S".into(),
+            tags: vec![],
+            max_dests: None,
+            min_dests: None,
+            note: None,
+        }
+    }
+
+    /// Scores `route` with every pair of `AAA`, `BBB` and `CCC` allowed, the pairs in `served`
+    /// already served and the pairs scored as in `scores`
+    fn route_score(
+        route: &[&str],
+        served: &[(&str, &str)],
+        scores: &[((&str, &str), i8)],
+    ) -> Option<i16> {
+        let mut config = Config::default();
+        let fd = FlightData {
+            flights: vec![],
+            old_world_airports: vec![],
+            new_world_airports: vec![],
+            coordinates: BTreeMap::new(),
+            towns: BTreeMap::new(),
+            timestamp: 0,
+        };
+        let mut destinations = HashMap::new();
+        let airports = ["AAA", "BBB", "CCC"];
+        let mut planner = Planner {
+            config: &mut config,
+            fd: &fd,
+            legs: airports
+                .iter()
+                .map(|a| {
+                    let nexts = airports.iter().filter(|b| *b != a).map(|b| gate(b));
+                    (gate(a), nexts.sorted().collect())
+                })
+                .collect(),
+            destinations: &mut destinations,
+            served: served
+                .iter()
+                .map(|(a, b)| Planner::pair(&(*a).into(), &(*b).into()))
+                .collect(),
+            pair_scores: scores
+                .iter()
+                .map(|((a, b), s)| (Planner::pair(&(*a).into(), &(*b).into()), *s))
+                .collect(),
+            endpoint_counts: Counter::new(),
+            max_per_gate: 1,
+        };
+        let route = route.iter().map(|a| gate(a)).collect::<Vec<_>>();
+        planner.route_score(&route).unwrap()
+    }
+
+    const SCORES: [((&str, &str), i8); 3] = [
+        (("AAA", "BBB"), 1),
+        (("BBB", "CCC"), 2),
+        (("AAA", "CCC"), 4),
+    ];
+
+    #[test]
+    fn credits_every_new_pair() {
+        assert_eq!(route_score(&["AAA", "BBB", "CCC"], &[], &SCORES), Some(7));
+        assert_eq!(
+            route_score(&["AAA", "BBB", "CCC"], &[("AAA", "BBB")], &SCORES),
+            Some(6)
+        );
+    }
+
+    #[test]
+    fn needs_a_new_non_adjacent_pair() {
+        assert_eq!(
+            route_score(&["AAA", "BBB", "CCC"], &[("CCC", "AAA")], &SCORES),
+            None
+        );
+        let scores = [
+            (("AAA", "BBB"), 1),
+            (("BBB", "CCC"), 2),
+            (("AAA", "CCC"), 0),
+        ];
+        assert_eq!(route_score(&["AAA", "BBB", "CCC"], &[], &scores), None);
+    }
+
+    #[test]
+    fn needs_every_pair_allowed() {
+        assert_eq!(route_score(&["AAA", "BBB", "DDD"], &[], &SCORES), None);
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Display,
+};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use smol_str::SmolStr;
+
+use crate::types::{
+    flight::Flight, flight_data::FlightData, AirlineName, AirportCode, FlightNumber, GateCode,
+};
+
+/// Who flies a leg of an itinerary
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Carrier {
+    /// A flight of the plan
+    Ours(FlightNumber),
+    /// A flight of another airline in Gatelogue, with its flight number
+    Other(AirlineName, SmolStr),
+}
+
+/// A flight from one airport to another, which may be part of a multi-stop flight.
+/// The gates of other airlines' flights are not known
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub carrier: Carrier,
+    pub from: (AirportCode, Option<GateCode>),
+    pub to: (AirportCode, Option<GateCode>),
+}
+
+/// The legs that can be flown from each airport
+fn legs(plan: &[Flight], fd: Option<&FlightData>) -> BTreeMap<AirportCode, Vec<Leg>> {
+    let mut legs = BTreeMap::<AirportCode, Vec<Leg>>::new();
+    // the flights of the plan go one way, so their stops are in order of travel
+    for f in plan {
+        for (from, to) in f.stops().tuple_combinations().filter(|(a, b)| a.0 != b.0) {
+            legs.entry(from.0.clone()).or_default().push(Leg {
+                carrier: Carrier::Ours(f.number),
+                from: (from.0.clone(), Some(from.1.clone())),
+                to: (to.0.clone(), Some(to.1.clone())),
+            });
+        }
+    }
+    for f in fd.into_iter().flat_map(|fd| &fd.flights) {
+        for (a, b) in f
+            .airports
+            .iter()
+            .tuple_combinations()
+            .filter(|(a, b)| a != b)
+        {
+            for (from, to) in [(a, b), (b, a)] {
+                legs.entry(from.clone()).or_default().push(Leg {
+                    carrier: Carrier::Other(f.airline.clone(), f.flight_number.clone()),
+                    from: (from.clone(), None),
+                    to: (to.clone(), None),
+                });
+            }
+        }
+    }
+    legs
+}
+
+/// The legs of a journey, in order
+#[derive(Debug, Clone)]
+pub struct Itinerary {
+    pub legs: Vec<Leg>,
+}
+
+impl Itinerary {
+    pub fn transfers(&self) -> usize {
+        self.legs.len() - 1
+    }
+}
+
+/// The number of transfers between different gates of the plan at the same airport
+fn walks(legs: &[Leg]) -> usize {
+    legs.iter()
+        .tuple_windows()
+        .filter(|(a, b)| matches!((&a.to.1, &b.from.1), (Some(g1), Some(g2)) if g1 != g2))
+        .count()
+}
+
+/// How good the legs are as (the start of) an itinerary, lower being better:
+/// fewer walks between gates, then fewer legs flown by other airlines, then by carrier.
+/// Adding legs never makes this lower
+fn rank(legs: &[Leg]) -> (usize, usize, Vec<&Carrier>) {
+    (
+        walks(legs),
+        legs.iter()
+            .filter(|l| matches!(l.carrier, Carrier::Other(..)))
+            .count(),
+        legs.iter().map(|l| &l.carrier).collect(),
+    )
+}
+
+/// The number of legs needed to get from each airport to `to`
+fn legs_to(
+    legs: &BTreeMap<AirportCode, Vec<Leg>>,
+    to: &AirportCode,
+) -> BTreeMap<AirportCode, usize> {
+    let mut reverse = BTreeMap::<&AirportCode, Vec<&AirportCode>>::new();
+    for leg in legs.values().flatten() {
+        reverse.entry(&leg.to.0).or_default().push(&leg.from.0);
+    }
+    let mut dist = BTreeMap::from([(to.clone(), 0)]);
+    let mut queue = VecDeque::from([to]);
+    while let Some(a) = queue.pop_front() {
+        let n = dist[a] + 1;
+        for b in reverse.get(a).into_iter().flatten() {
+            if !dist.contains_key(*b) {
+                dist.insert((*b).clone(), n);
+                queue.push_back(b);
+            }
+        }
+    }
+    dist
+}
+
+/// The best itineraries with exactly `n` more legs from the end of `path` to `to`, kept in
+/// `best` in order of [`rank`], which holds no more than `limit`
+#[expect(clippy::too_many_arguments)]
+fn search(
+    legs: &BTreeMap<AirportCode, Vec<Leg>>,
+    dist: &BTreeMap<AirportCode, usize>,
+    path: &mut Vec<Leg>,
+    from: &AirportCode,
+    to: &AirportCode,
+    n: usize,
+    limit: usize,
+    best: &mut Vec<Itinerary>,
+) {
+    // once `best` is full, paths that already rank below the worst of it can't get better
+    if best.len() >= limit
+        && best.last().is_some_and(|worst| {
+            let (walks, other_legs, carriers) = rank(&worst.legs);
+            rank(path) > (walks, other_legs, carriers[..path.len()].to_vec())
+        })
+    {
+        return;
+    }
+    if n == 0 {
+        if from == to {
+            let i = best.partition_point(|i| rank(&i.legs) <= rank(path));
+            best.insert(i, Itinerary { legs: path.clone() });
+            best.truncate(limit);
+        }
+        return;
+    }
+    for leg in legs.get(from).into_iter().flatten() {
+        let visited = |a: &AirportCode| path.iter().any(|l| l.from.0 == *a) || *a == *from;
+        // staying on the same flight is already covered by the leg to its later stop
+        if path.last().is_some_and(|l| l.carrier == leg.carrier)
+            || visited(&leg.to.0)
+            || dist.get(&leg.to.0).is_none_or(|d| *d > n - 1)
+        {
+            continue;
+        }
+        path.push(leg.clone());
+        search(legs, dist, path, &leg.to.0, to, n - 1, limit, best);
+        path.pop();
+    }
+}
+
+/// The best `limit` itineraries from `from` to `to` with at most `max_transfers` transfers,
+/// using the flights of `plan` and, if `fd` is given, the flights of other airlines.
+/// Itineraries with fewer transfers, then fewer walks between gates, then fewer legs flown
+/// by other airlines come first
+pub fn find(
+    plan: &[Flight],
+    fd: Option<&FlightData>,
+    from: &AirportCode,
+    to: &AirportCode,
+    max_transfers: usize,
+    limit: usize,
+) -> Result<Vec<Itinerary>> {
+    let legs = legs(plan, fd);
+    for a in [from, to] {
+        if !legs.values().flatten().any(|l| l.from.0 == *a) {
+            return Err(anyhow!("No flights fly to or from `{a}`"));
+        }
+    }
+    if from == to {
+        return Err(anyhow!(
+            "`{from}` is both the start and the end of the journey"
+        ));
+    }
+    let dist = legs_to(&legs, to);
+    let mut itineraries = vec![];
+    for n in 1..=max_transfers + 1 {
+        let remaining = limit - itineraries.len();
+        if remaining == 0 {
+            break;
+        }
+        let mut best = vec![];
+        search(&legs, &dist, &mut vec![], from, to, n, remaining, &mut best);
+        itineraries.extend(best);
+    }
+    Ok(itineraries)
+}
+
+impl Display for Leg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stop = |(a, g): &(AirportCode, Option<GateCode>)| {
+            g.as_ref()
+                .map_or_else(|| a.to_string(), |g| format!("{a} {g}"))
+        };
+        match &self.carrier {
+            Carrier::Ours(n) => write!(f, "{n}")?,
+            Carrier::Other(airline, n) => write!(f, "{airline} {n}")?,
+        }
+        write!(f, ": {} -> {}", stop(&self.from), stop(&self.to))
+    }
+}
+
+impl Display for Itinerary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let n = self.transfers();
+        writeln!(f, "{n} transfer{}", if n == 1 { "" } else { "s" })?;
+        for (i, leg) in self.legs.iter().enumerate() {
+            if let Some(prev) = i.checked_sub(1).map(|i| &self.legs[i]) {
+                let airport = &leg.from.0;
+                let transfer = match (&prev.to.1, &leg.from.1) {
+                    (Some(g1), Some(g2)) if g1 == g2 => format!("stay at gate {g1}"),
+                    (Some(g1), Some(g2)) => format!("walk from gate {g1} to gate {g2}"),
+                    _ => "change flights".to_owned(),
+                };
+                writeln!(f, "  {transfer} at {airport}")?;
+            }
+            writeln!(f, "  {leg}")?;
+        }
+        Ok(())
+    }
+}
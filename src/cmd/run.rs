@@ -1,16 +1,22 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
 
 use anyhow::{anyhow, Result};
 use itertools::Itertools;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 
 use crate::{
-    fbp,
+    cmd::{
+        connectivity::{self, Network, Violation},
+        multi_stop,
+    },
     types::{
         config::Config, flight::Flight, flight_type::FlightType, flight_utils::FlightUtils,
-        fng::FlightNumberGenerator, gate::Gate, AirportCode,
+        fng::FlightNumberGenerators, gate::Gate, rule::Route, size, AirportCode,
     },
-    utils::{for_both, for_both_permutations, AnyAllBool},
+    utils::{for_both, for_both_permutations, seeded_hash},
     FlightData,
 };
 
@@ -19,11 +25,13 @@ fn sort_gates(
     config: &mut Config,
     fd: &FlightData,
     old_plan: Option<&Vec<Flight>>,
+    seed: Option<u64>,
 ) -> Result<Vec<(Gate, Gate, i8, FlightType)>> {
     Ok(x.into_iter()
         .map(|(g1, g2, _, ty)| {
             let s = (&g1, &g2).score(config, fd)?;
-            let existed = old_plan.is_some_and(|old_plan| old_plan
+            let existed = old_plan.is_some_and(|old_plan| {
+                old_plan
                     .iter()
                     .filter(|f| {
                         (f.airport1 == (g1.airport.clone(), g1.code.clone())
@@ -31,144 +39,80 @@ fn sort_gates(
                             || (f.airport1 == (g2.airport.clone(), g2.code.clone())
                                 && f.airport2 == (g1.airport.clone(), g1.code.clone()))
                     })
-                    .count() > 0);
+                    .count()
+                    > 0
+            });
             Ok((g1, g2, s, ty, existed))
         })
         .collect::<Result<Vec<_>>>()?
         .into_iter()
-        .sorted_by(|(_, _, mut s1, _, existed1), (_, _, mut s2, _, existed2)| {
-            if *existed1 {
-                s1 += 1;
-            }
-            if *existed2 {
-                s2 += 1;
-            }
-            s1.cmp(&s2)
+        // ties are broken by the seeded order (if any) and then by the gates themselves,
+        // so that the plan never depends on the order the gates were read in.
+        // the list is popped from the back, so the gates are reversed to pop the smallest first
+        .sorted_by_cached_key(|(g1, g2, s, _, existed)| {
+            (
+                *s + i8::from(*existed),
+                seed.map(|seed| seeded_hash(seed, &(g1, g2))),
+                Reverse((g1.to_owned(), g2.to_owned())),
+            )
         })
         .map(|(g1, g2, s, ty, _)| (g1, g2, s, ty))
         .collect::<Vec<_>>())
 }
 
-#[expect(clippy::too_many_lines)]
-pub fn run(
-    config: &mut Config,
-    fd: &FlightData,
-    old_plan: Option<&Vec<Flight>>,
-) -> Result<Vec<Flight>> {
-    let hubs = config.hubs()?;
-    let restricted_between = config.restricted_between.clone();
-    let restricted_to = config.restricted_to.clone();
-    let gate_allowed_dests = config.gate_allowed_dests.clone();
-    let gate_denied_dests = config.gate_denied_dests.clone();
-    let preferred_between = config.preferred_between.clone();
-    let preferred_to = config.preferred_to.clone();
-    let no_dupes = config.no_dupes.clone();
-    let mut possible_flights = config
-        .gates()?
-        .into_iter()
-        .tuple_combinations::<(_, _)>()
-        .filter(|(g1, g2)| {
-            !restricted_between.iter().any(|re| {
-                vec![g1.airport.clone(), g2.airport.clone()]
-                    .into_iter()
-                    .all(|a| re.contains(&a))
-            })
-        })
-        .filter(|(g1, g2)| g1.airport != g2.airport && g1.size == g2.size)
-        .filter(fbp!(
-            filter | g1: &Gate,
-            g2: &Gate | {
-                !restricted_to
-                    .get(&*g1.airport)
-                    .unwrap_or(&vec![])
-                    .contains(&g2.airport)
-            }
-        ))
-        .filter(fbp!(
-            filter | g1: &Gate,
-            g2: &Gate | {
-                gate_allowed_dests.get(&*g1.airport).is_none_or(|gates| gates.get(&*g1.code).is_none_or(|gate| gate.contains(&g2.airport)))
-            }
-        ))
-        .filter(fbp!(
-            filter | g1: &Gate,
-            g2: &Gate | {
-                gate_denied_dests.get(&*g1.airport).is_none_or(|gates| gates.get(&*g1.code).is_none_or(|gate| !gate.contains(&g2.airport)))
-            }
-        ))
-        .map(|(g1, g2)| {
-            let ty = (&g1, &g2).get_flight_type(config, fd)?;
-            Ok((g1, g2, 0i8, ty))
-        })
-        .filter_ok(|(g1, g2, _, ty)| {
-            if preferred_between
-                .iter()
-                .any(|a| a.contains(&g1.airport) && a.contains(&g2.airport))
-                || preferred_to
-                    .get(&g1.airport)
-                    .is_some_and(|a| a.contains(&g2.airport))
-                || preferred_to
-                    .get(&g2.airport)
-                    .is_some_and(|a| a.contains(&g1.airport))
-            {
-                true
-            } else if no_dupes.contains(&g1.airport) || no_dupes.contains(&g2.airport) {
-                ![
-                    FlightType::ExistingH2H,
-                    FlightType::ExistingH2N,
-                    FlightType::ExistingN2N,
-                ]
-                .contains(ty)
-            } else {
-                true
-            }
-        })
-        .collect::<Result<Vec<_>>>()?;
-
-    let mut h2h_fng = FlightNumberGenerator::new(config.range_h2h.clone());
-    let mut h2n_fng = HashMap::new();
-    let mut n2n_fng = FlightNumberGenerator::new(config.range_n2n.clone());
-
-    let mut destinations: HashMap<Gate, Vec<AirportCode>> = HashMap::new();
-    let mut flights: Vec<Flight> = vec![];
-
-    possible_flights = sort_gates(possible_flights, config, fd, old_plan)?;
+/// The flights planned so far
+#[derive(Default)]
+struct Plan {
+    fngs: FlightNumberGenerators,
+    /// The destinations of each gate, with the flight type of each
+    destinations: HashMap<Gate, Vec<(AirportCode, FlightType)>>,
+    flights: Vec<Flight>,
+}
 
-    while let Some((mut g1, mut g2, mut s, ty)) = possible_flights.pop() {
-        if hubs.contains(&g2.airport) && !hubs.contains(&g1.airport) {
+impl Plan {
+    /// The number of destinations of `g`
+    fn dests(&self, g: &Gate) -> usize {
+        self.destinations.get(g).map_or(0, Vec::len)
+    }
+    /// The number of different airports that `gates` fly to
+    fn airports_served(&self, gates: &[Gate]) -> usize {
+        gates
+            .iter()
+            .filter_map(|g| self.destinations.get(g))
+            .flatten()
+            .map(|(a, _)| a)
+            .unique()
+            .count()
+    }
+    /// Adds flights in both directions between `g1` and `g2`, returning why they were rejected
+    /// if they were. `guaranteed` flights are added even if their score is too low,
+    /// as they are needed for a minimum number of destinations
+    #[expect(clippy::too_many_lines)]
+    fn add(
+        &mut self,
+        config: &mut Config,
+        (mut g1, mut g2, mut s, ty): (Gate, Gate, i8, FlightType),
+        guaranteed: bool,
+    ) -> Result<Option<String>> {
+        let sizes = config.sizes.clone();
+        if config.tier(&g2.airport, &g2.size)? < config.tier(&g1.airport, &g1.size)? {
             (g1, g2) = (g2.clone(), g1.clone());
         }
-        if for_both(&g1, &g2, |g| {
-            destinations.get(g).unwrap_or(&vec![]).len()
-                >= *config
-                    .max_dests_per_gate
-                    .get(&g.airport)
-                    .unwrap_or(&u8::MAX) as usize
-        })
-        .any()
-        {
-            continue;
+        let (full1, full2) = for_both(&g1, &g2, |g| {
+            (self.dests(g) >= config.max_dests(g).unwrap_or(usize::MAX))
+                .then(|| format!("{g} hit its max_dests"))
+        });
+        if let Some(reason) = full1.or(full2) {
+            return Ok(Some(reason));
         }
-        s -= (destinations.get(&g1).unwrap_or(&vec![]).len() as i8)
-            .min(destinations.get(&g2).unwrap_or(&vec![]).len() as i8);
-        if s < 0 {
-            continue;
+        s -= (self.dests(&g1) as i8).min(self.dests(&g2) as i8);
+        if s < 0 && !guaranteed {
+            return Ok(Some("score too low".into()));
         }
-        let (max1, max2) = for_both(&g1, &g2, |g| match ty {
-            FlightType::ExistingH2H | FlightType::NonExistingH2H => config.max_h2h,
-            FlightType::ExistingH2N | FlightType::NonExistingH2N => {
-                if hubs.contains(&g.airport) {
-                    config.max_h2n_hub
-                } else {
-                    config.max_h2n_nonhub
-                }
-            }
-            FlightType::ExistingN2N | FlightType::NonExistingN2N => config.max_n2n,
-        });
+        let (max1, max2) = (config.type_max(&g1, &ty)?, config.type_max(&g2, &ty)?);
 
-        if flights.iter().any(|f| {
-            (f.airport1.0 == g1.airport && f.airport2.0 == g2.airport)
-                || (f.airport1.0 == g2.airport && f.airport2.0 == g1.airport)
+        if self.flights.iter().any(|f| {
+            f.stops().any(|(a, _)| *a == g1.airport) && f.stops().any(|(a, _)| *a == g2.airport)
         }) {
             trace!(
                 "Rejected ({} {}): {} {} <-> {} {} (already exists)",
@@ -179,88 +123,59 @@ pub fn run(
                 g2.airport,
                 g2.code
             );
-            continue;
+            return Ok(Some(format!(
+                "{} and {} are already connected",
+                g1.airport, g2.airport
+            )));
         }
 
-        let (g1_hardmax, g2_hardmax) = for_both(&g1, &g2, |g| {
-            (if let Some(n) = config.max_dests_per_gate.get(&g.airport) {
-                *n
-            } else if hubs.contains(&g.airport) {
-                config.hard_max_hub
-            } else {
-                config.hard_max_nonhub
-            }) as usize
-        });
-        if for_both_permutations(
+        let (g1_hardmax, g2_hardmax) = (config.hard_max(&g1)?, config.hard_max(&g2)?);
+        let (hit1, hit2) = for_both_permutations(
             &(&g1, &g1_hardmax),
             &(&g2, &g2_hardmax),
             |(g, hardmax), (og, _)| {
-                if destinations.get(g).unwrap_or(&vec![]).len() >= **hardmax {
+                (self.dests(g) >= **hardmax).then(|| {
                     debug!(
                         "Rejected ({} {}): {} {} <-> {} {} ({2} hit max limit of {})",
                         ty, og.size, g.airport, g.code, og.airport, og.code, hardmax
                     );
-                    true
-                } else {
-                    false
-                }
+                    format!("{g} hit max limit of {hardmax}")
+                })
             },
-        )
-        .any()
-        {
-            continue;
+        );
+        if let Some(reason) = hit1.or(hit2) {
+            return Ok(Some(reason));
         }
-        if for_both_permutations(&(&g1, max1), &(&g2, max2), |(g, max), (og, _)| {
-            if destinations
-                .get(g)
-                .unwrap_or(&vec![])
-                .iter()
-                .filter(|d| (&g.airport, *d).get_flight_type(config, fd).unwrap() == ty)
-                .count()
-                >= *max as usize
-            {
-                debug!(
-                    "Rejected ({} {}): {} {} <-> {} {} ({2} hit max type limit of {})",
-                    ty, og.size, g.airport, g.code, og.airport, og.code, max
-                );
-                true
-            } else {
-                false
-            }
-        })
-        .any()
-        {
-            continue;
+        let (hit1, hit2) =
+            for_both_permutations(&(&g1, max1), &(&g2, max2), |(g, max), (og, _)| {
+                (self
+                    .destinations
+                    .get(g)
+                    .unwrap_or(&vec![])
+                    .iter()
+                    .filter(|(_, dty)| *dty == ty)
+                    .count()
+                    >= *max)
+                    .then(|| {
+                        debug!(
+                            "Rejected ({} {}): {} {} <-> {} {} ({2} hit max type limit of {})",
+                            ty, og.size, g.airport, g.code, og.airport, og.code, max
+                        );
+                        format!("{g} hit max {} limit of {max}", ty.pair_name())
+                    })
+            });
+        if let Some(reason) = hit1.or(hit2) {
+            return Ok(Some(reason));
         }
 
         for_both_permutations(&g1, &g2, |g1, g2| {
-            destinations
+            self.destinations
                 .entry(g1.to_owned())
                 .or_default()
-                .push(g2.airport.clone());
+                .push((g2.airport.clone(), ty.clone()));
         });
-        let fng = match ty {
-            FlightType::ExistingH2H | FlightType::NonExistingH2H => &mut h2h_fng,
-            FlightType::ExistingH2N | FlightType::NonExistingH2N => h2n_fng
-                .entry(
-                    (if config.range_h2n.contains_key(&*g1.airport.clone()) {
-                        &g1
-                    } else {
-                        &g2
-                    })
-                    .airport.clone(),
-                )
-                .or_insert_with(|| {
-                    FlightNumberGenerator::new(
-                        config
-                            .range_h2n
-                            .get(&*g1.airport.clone())
-                            .unwrap_or_else(|| &config.range_h2n[&*g2.airport.clone()])
-                            .to_owned(),
-                    )
-                }),
-            FlightType::ExistingN2N | FlightType::NonExistingN2N => &mut n2n_fng,
-        };
+        let flights = &mut self.flights;
+        let fng = self.fngs.get(config, &ty, &g1.airport, &g2.airport)?;
 
         let fn1 = fng.find(|a| !flights.iter().map(|f| f.number).contains(a));
         let fn2 = if config.both_dir_same_num {
@@ -283,9 +198,10 @@ pub fn run(
                     },
                     airport1: (g1.airport.clone(), g1.code.clone()),
                     airport2: (g2.airport.clone(), g2.code.clone()),
-                    size: g1.size.clone(),
+                    via: vec![],
+                    size: size::plane_size(&sizes, g1, g2).unwrap_or_else(|| g1.size.clone()),
                     score: s,
-                    ty,
+                    ty: ty.clone(),
                 };
                 info!(
                     "{} ({} {}): {} {} -> {} {}, {}",
@@ -296,8 +212,317 @@ pub fn run(
             });
         flight1?;
         flight2?;
-        //possible_flights = sort_gates(possible_flights, config, fd)?;
+        Ok(None)
+    }
+}
+
+/// A minimum number of destinations for a gate or an airport
+struct Minimum {
+    /// The gate or airport, for messages
+    name: String,
+    gates: Vec<Gate>,
+    min: usize,
+}
+
+/// The minimums from `min_dests`, `min_dests_per_gate` and the `min_dests` of gates,
+/// gates first
+fn minimums(config: &mut Config) -> Result<Vec<Minimum>> {
+    let gates = config.gates()?;
+    let per_gate = gates.iter().sorted().map(|g| Minimum {
+        name: format!("gate {} {}", g.airport, g.code),
+        gates: vec![g.clone()],
+        min: config.min_dests(g),
+    });
+    let per_airport = config.min_dests.iter().map(|(a, min)| Minimum {
+        name: format!("airport {a}"),
+        gates: gates.iter().filter(|g| g.airport == *a).cloned().collect(),
+        min: usize::from(*min),
+    });
+    Ok(per_gate.chain(per_airport).filter(|m| m.min > 0).collect())
+}
+
+/// Why the gates of `minimum` cannot fly to the gates they have no possible flights with
+fn blocked_reasons(config: &mut Config, fd: &FlightData, minimum: &Minimum) -> Result<Vec<String>> {
+    let mut reasons = vec![];
+    for g1 in &minimum.gates {
+        for g2 in config.gates()?.iter().filter(|g| g.airport != g1.airport) {
+            if size::plane_size(&config.sizes, g1, g2).is_none() {
+                reasons.push("no plane size in common".to_owned());
+                continue;
+            }
+            let route = Route::between_gates(config, fd, g1, g2)?;
+            if let Some(rule) = config.judge(&route).forbidden_by {
+                reasons.push(format!("forbidden by {rule}"));
+            }
+        }
+    }
+    Ok(reasons)
+}
+
+/// Adds the best flights that are allowed for each gate and airport with a minimum number of
+/// destinations, until the minimum is met. Minimums that cannot be met are warned about,
+/// with the reasons that flights could not be added
+fn guarantee_minimums(
+    config: &mut Config,
+    fd: &FlightData,
+    possible_flights: &[(Gate, Gate, i8, FlightType)],
+    plan: &mut Plan,
+) -> Result<()> {
+    for minimum in minimums(config)? {
+        if plan.airports_served(&minimum.gates) >= minimum.min {
+            continue;
+        }
+        info!("Planning flights for the min_dests of {}", minimum.name);
+        let mut reasons = vec![];
+        // possible flights are sorted with the best last
+        for flight in possible_flights
+            .iter()
+            .rev()
+            .filter(|(g1, g2, _, _)| minimum.gates.contains(g1) || minimum.gates.contains(g2))
+        {
+            if let Some(reason) = plan.add(config, flight.clone(), true)? {
+                reasons.push(reason);
+            }
+            if plan.airports_served(&minimum.gates) >= minimum.min {
+                break;
+            }
+        }
+        let served = plan.airports_served(&minimum.gates);
+        if served < minimum.min {
+            reasons.extend(blocked_reasons(config, fd, &minimum)?);
+            warn!(
+                "Could only give {} {served} of the {} destinations in its min_dests, as flights were rejected for: {}",
+                minimum.name,
+                minimum.min,
+                if reasons.is_empty() {
+                    "having no other gates to fly to".to_owned()
+                } else {
+                    reasons
+                        .into_iter()
+                        .counts()
+                        .into_iter()
+                        .sorted_by_key(|(reason, n)| (Reverse(*n), reason.clone()))
+                        .map(|(reason, n)| {
+                            format!("{reason} ({n} flight{})", if n == 1 { "" } else { "s" })
+                        })
+                        .join(", ")
+                }
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Adds the best flights that are allowed to fix each way in which the plan does not meet
+/// `connectivity`, returning the ways that could not be fixed. Flights that fix a way
+/// completely are tried first, then ones that only get closer to fixing it
+fn repair_connectivity(
+    config: &mut Config,
+    possible_flights: &[(Gate, Gate, i8, FlightType)],
+    plan: &mut Plan,
+) -> Result<Vec<Violation>> {
+    let hubs = connectivity::hubs(config)?;
+    let airports = config.airports()?;
+    let mut unfixable = vec![];
+    loop {
+        let Some(violation) = connectivity::violations(config, &plan.flights)?
+            .into_iter()
+            .find(|v| !unfixable.contains(v))
+        else {
+            break;
+        };
+        info!("Planning flights as {violation}");
+        let network = Network::new(&airports, &plan.flights);
+        let (x, y, now) = match &violation {
+            Violation::NoHub(x) => (x.clone(), None, usize::MAX),
+            Violation::Transfers(x, y, transfers) => (
+                x.clone(),
+                Some(y.clone()),
+                transfers.map_or(usize::MAX, |n| n + 1),
+            ),
+        };
+        let hops = y.as_ref().map(|y| (network.hops(&x), network.hops(y)));
+        // the number of flights between the ends of the violation if `a` flew to `b`,
+        // if that is fewer than now
+        let hops_with = |a: &AirportCode, b: &AirportCode| {
+            let n = match &hops {
+                None => (*a == x && hubs.contains(b)).then_some(0),
+                Some((from_x, from_y)) => {
+                    if *a == x {
+                        from_y.get(b).copied()
+                    } else if Some(a) == y.as_ref() {
+                        from_x.get(b).copied()
+                    } else {
+                        None
+                    }
+                }
+            };
+            n.map(|n| n + 1).filter(|n| *n < now)
+        };
+        let mut fixed = false;
+        // possible flights are sorted with the best last
+        let candidates = possible_flights
+            .iter()
+            .rev()
+            .filter_map(|f| {
+                [
+                    hops_with(&f.0.airport, &f.1.airport),
+                    hops_with(&f.1.airport, &f.0.airport),
+                ]
+                .into_iter()
+                .flatten()
+                .min()
+                .map(|n| (n, f))
+            })
+            .sorted_by_key(|(n, _)| *n)
+            .collect::<Vec<_>>();
+        for (_, flight) in candidates {
+            if plan.add(config, flight.clone(), true)?.is_none() {
+                fixed = true;
+                break;
+            }
+        }
+        if !fixed {
+            unfixable.push(violation);
+        }
+    }
+    Ok(unfixable)
+}
+
+pub fn run(
+    config: &mut Config,
+    fd: &FlightData,
+    old_plan: Option<&Vec<Flight>>,
+    seed: Option<u64>,
+) -> Result<Vec<Flight>> {
+    let sizes = config.sizes.clone();
+    let mut possible_flights = config
+        .gates()?
+        .into_iter()
+        .tuple_combinations::<(_, _)>()
+        .filter(|(g1, g2)| g1.airport != g2.airport && size::plane_size(&sizes, g1, g2).is_some())
+        .map(|(g1, g2)| {
+            let route = Route::between_gates(config, fd, &g1, &g2)?;
+            let ty = route.ty.clone();
+            let forbidden_by = config.judge(&route).forbidden_by;
+            if let Some(rule) = &forbidden_by {
+                trace!("{g1} <-> {g2} is forbidden by {rule}");
+            }
+            Ok(forbidden_by.is_none().then_some((g1, g2, 0i8, ty)))
+        })
+        .flatten_ok()
+        .collect::<Result<Vec<_>>>()?;
+    let allowed_legs = possible_flights
+        .iter()
+        .flat_map(|(g1, g2, _, _)| [(g1.clone(), g2.clone()), (g2.clone(), g1.clone())])
+        .collect::<HashSet<_>>();
+
+    let mut plan = Plan::default();
+
+    possible_flights = sort_gates(possible_flights, config, fd, old_plan, seed)?;
+
+    guarantee_minimums(config, fd, &possible_flights, &mut plan)?;
+
+    if config.multi_stop.enabled {
+        multi_stop::plan(
+            config,
+            fd,
+            &allowed_legs,
+            &mut plan.destinations,
+            &mut plan.flights,
+        )?;
+    }
+
+    let all_flights = possible_flights.clone();
+    while let Some(flight) = possible_flights.pop() {
+        plan.add(config, flight, false)?;
+    }
+
+    let violations = repair_connectivity(config, &all_flights, &mut plan)?;
+    if !violations.is_empty() {
+        return Err(anyhow!(
+            "The plan does not meet the connectivity requirements, and no allowed flights could be added to fix this:\n{}{}",
+            violations.iter().take(20).map(|v| format!("  - {v}\n")).join(""),
+            if violations.len() > 20 {
+                format!("  ... and {} more", violations.len() - 20)
+            } else {
+                String::new()
+            }
+        ));
+    }
+
+    Ok(plan.flights)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn gate(airport: &str, code: &str) -> Gate {
+        Gate {
+            airport: airport.into(),
+            code: code.into(),
+            size: "S".into(),
+            tags: vec![],
+            max_dests: None,
+            min_dests: None,
+            note: None,
+        }
+    }
+
+    fn flight_data() -> FlightData {
+        FlightData {
+            flights: vec![],
+            old_world_airports: vec![],
+            new_world_airports: vec![],
+            coordinates: BTreeMap::new(),
+            towns: BTreeMap::new(),
+            timestamp: 0,
+        }
+    }
+
+    /// Every pair of gates in `pairs` scores the same, so only the tie-breaking orders them
+    fn sorted(pairs: &[(&str, &str)], seed: Option<u64>) -> Vec<(AirportCode, AirportCode)> {
+        let mut config = Config::default();
+        let ty = FlightType {
+            tier1: "N".into(),
+            tier2: "N".into(),
+            existing: false,
+        };
+        let gates = pairs
+            .iter()
+            .map(|(a1, a2)| (gate(a1, "1"), gate(a2, "1"), 0, ty.clone()))
+            .collect();
+        sort_gates(gates, &mut config, &flight_data(), None, seed)
+            .unwrap()
+            .into_iter()
+            .map(|(g1, g2, _, _)| (g1.airport, g2.airport))
+            .collect()
     }
 
-    Ok(flights)
+    #[test]
+    fn ties_pop_the_smallest_gates_first() {
+        let pairs = [("BBB", "CCC"), ("AAA", "CCC"), ("AAA", "BBB")];
+        let order = sorted(&pairs, None);
+        assert_eq!(
+            order,
+            [("BBB", "CCC"), ("AAA", "CCC"), ("AAA", "BBB")].map(|(a, b)| (a.into(), b.into()))
+        );
+    }
+
+    #[test]
+    fn ties_do_not_depend_on_input_order() {
+        let pairs = [
+            ("AAA", "BBB"),
+            ("AAA", "CCC"),
+            ("BBB", "CCC"),
+            ("CCC", "DDD"),
+        ];
+        let reversed = pairs.iter().rev().copied().collect::<Vec<_>>();
+        for seed in [None, Some(1), Some(2)] {
+            assert_eq!(sorted(&pairs, seed), sorted(&reversed, seed));
+        }
+    }
 }
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use schemars::generate::SchemaSettings;
+use serde_json::json;
+
+use crate::types::{config::Config, size, Size};
+
+/// The JSON Schema of the configuration file, in draft 7 as it is the most widely supported by editors.
+/// If `sizes` is not empty, gate sizes must be one of them
+pub fn schema(sizes: &[Size]) -> Result<String> {
+    let mut schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<Config>();
+    if !sizes.is_empty() {
+        if let Some(defs) = schema
+            .as_object_mut()
+            .and_then(|s| s.get_mut("definitions"))
+            .and_then(|d| d.as_object_mut())
+        {
+            defs.insert(
+                size::SCHEMA_NAME.into(),
+                json!({
+                    "description": "The size of a gate",
+                    "enum": sizes
+                }),
+            );
+        }
+    }
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
@@ -1,72 +1,367 @@
+use std::{collections::BTreeMap, fmt::Display};
+
 use anyhow::Result;
+use clap::ValueEnum;
 use itertools::Itertools;
+use serde::Serialize;
 
 use crate::{
-    types::{flight::Flight, flight_type::FlightType},
-    Config,
+    cmd::connectivity,
+    types::{flight::Flight, gate::Gate, AirportCode, FlightNumber, GroupName, Size},
+    Config, FlightData,
 };
 
-pub fn get_stats(res: &[Flight], config: &mut Config) -> Result<String> {
-    let flights = res.len();
-    let flight_pairs = res.len() / 2;
-    let airports = config.airports()?.len();
-    let gates = config.gates()?.len();
-    let hubs = config.hubs()?;
-    let hard_max_hub = config.hard_max_hub;
-    let hard_max_nonhub = config.hard_max_nonhub;
-
-    let full_gates = config.gates()?.into_iter().filter(|g| {
-        res.iter()
-            .filter(|f| f.airport1 == (g.airport.clone(), g.code.clone()))
-            .count()
-            >= if hubs.contains(&g.airport) {
-                hard_max_hub
-            } else {
-                hard_max_nonhub
-            } as usize
-    });
-    let empty_gates = config.gates()?.into_iter().filter(|g| {
-        res.iter()
-            .filter(|f| f.airport1 == (g.airport.clone(), g.code.clone()))
-            .count()
-            == 0
-    });
-    let duped_flights = res
+/// How statistics are printed
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum StatsFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Statistics about a flight plan
+#[derive(Debug, Serialize)]
+pub struct PlanStats {
+    /// The number of flights, counting each direction separately
+    pub flights: usize,
+    /// The number of pairs of flights in opposite directions
+    pub flight_pairs: usize,
+    /// The number of airports with gates
+    pub airports: usize,
+    pub gates: usize,
+    /// Gates with as many destinations as they can have
+    pub full_gates: Vec<String>,
+    /// Gates without any destinations
+    pub empty_gates: Vec<String>,
+    /// The percentage of flights that other airlines also fly
+    pub duplicate_percent: f64,
+    /// The number of flights of each flight type, e.g. `H2Nn`
+    pub per_type: BTreeMap<String, usize>,
+    /// The number of flights that stop at each hub (the airports in the first tier)
+    pub per_hub: BTreeMap<AirportCode, usize>,
+    /// The number of flights with each plane size
+    pub per_size: BTreeMap<Size, usize>,
+    /// The number of gates with each number of destinations
+    pub dests_per_gate: BTreeMap<usize, usize>,
+    /// The unique and duplicate flights at each airport with gates
+    pub per_airport: BTreeMap<AirportCode, AirportStats>,
+    /// How much of each range of flight numbers is used
+    pub ranges: Vec<RangeStats>,
+    /// Airports in Gatelogue that the plan doesn't fly to,
+    /// with the number of flights that other airlines have there
+    pub unserved_airports: BTreeMap<AirportCode, usize>,
+    /// The statistics of each airport group
+    pub groups: BTreeMap<GroupName, GroupStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AirportStats {
+    /// Flights that no other airline flies
+    pub unique: usize,
+    /// Flights that other airlines also fly
+    pub duplicate: usize,
+    /// The percentage of the airport's flights that other airlines also fly
+    pub duplicate_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RangeStats {
+    /// What the range is for, e.g. `H2N AAA` or `multi_stop`
+    pub name: String,
+    pub start: FlightNumber,
+    pub end: FlightNumber,
+    /// The number of flight numbers in the range that are used
+    pub used: usize,
+    /// The percentage of flight numbers in the range that are used
+    pub used_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupStats {
+    pub airports: usize,
+    pub gates: usize,
+    pub empty_gates: usize,
+    /// Flights that stop at an airport in the group
+    pub flights: usize,
+    /// Flights that only stop at airports in the group
+    pub flights_within: usize,
+    pub duplicate_percent: f64,
+}
+
+/// `n` as a percentage of `total`, or 0 if `total` is 0
+fn percent(n: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        n as f64 / total as f64 * 100.0
+    }
+}
+
+/// The number of flights, and the number of them that other airlines also fly
+fn duplicates<'a>(flights: impl IntoIterator<Item = &'a Flight>) -> (usize, usize) {
+    flights
+        .into_iter()
+        .fold((0, 0), |(n, d), f| (n + 1, d + usize::from(f.ty.existing)))
+}
+
+impl PlanStats {
+    /// The statistics of the flights `res`, planned with `config` and flight data `fd`
+    pub fn new(res: &[Flight], config: &mut Config, fd: &FlightData) -> Result<Self> {
+        let gates = config.gates()?;
+        let airports = config.airports()?;
+
+        // every leg from a gate is one of its destinations
+        let mut dests = gates
+            .iter()
+            .map(|g| (g, 0usize))
+            .collect::<BTreeMap<_, _>>();
+        for (from, _) in res.iter().flat_map(Flight::legs) {
+            if let Some(g) = gates
+                .iter()
+                .find(|g| (&g.airport, &g.code) == (&from.0, &from.1))
+            {
+                *dests.entry(g).or_default() += 1;
+            }
+        }
+        let mut full_gates = vec![];
+        for (g, n) in &dests {
+            // the hard maximum of a gate is its max_dests if it has one
+            if *n >= config.hard_max(g)? {
+                full_gates.push(g.to_string());
+            }
+        }
+        let empty_gates = dests
+            .iter()
+            .filter(|(_, n)| **n == 0)
+            .map(|(g, _)| (*g).clone())
+            .collect::<Vec<_>>();
+
+        let stops_at = |a: &AirportCode, f: &Flight| f.stops().any(|(s, _)| s == a);
+        let per_hub = connectivity::hubs(config)?
+            .into_iter()
+            .map(|h| {
+                let n = res.iter().filter(|f| stops_at(&h, f)).count();
+                (h, n)
+            })
+            .collect();
+        let per_airport = airports
+            .iter()
+            .map(|a| {
+                let (n, duplicate) = duplicates(res.iter().filter(|f| stops_at(a, f)));
+                let stats = AirportStats {
+                    unique: n - duplicate,
+                    duplicate,
+                    duplicate_percent: percent(duplicate, n),
+                };
+                (a.clone(), stats)
+            })
+            .collect();
+        let served = res
+            .iter()
+            .flat_map(|f| f.stops().map(|(a, _)| a))
+            .collect::<Vec<_>>();
+        let unserved_airports = fd
+            .old_world_airports
+            .iter()
+            .chain(&fd.new_world_airports)
+            .filter(|a| !served.contains(a))
+            .map(|a| {
+                let n = fd.flights.iter().filter(|f| f.airports.contains(a)).count();
+                (a.clone(), n)
+            })
+            .collect();
+        let (n, duplicate) = duplicates(res);
+
+        Ok(Self {
+            flights: res.len(),
+            flight_pairs: res.len() / 2,
+            airports: airports.len(),
+            gates: gates.len(),
+            full_gates: full_gates.into_iter().sorted().collect(),
+            empty_gates: empty_gates
+                .iter()
+                .map(ToString::to_string)
+                .sorted()
+                .collect(),
+            duplicate_percent: percent(duplicate, n),
+            per_type: res
+                .iter()
+                .map(|f| f.ty.to_string())
+                .counts()
+                .into_iter()
+                .collect(),
+            per_hub,
+            per_size: res
+                .iter()
+                .map(|f| f.size.clone())
+                .counts()
+                .into_iter()
+                .collect(),
+            dests_per_gate: dests.values().copied().counts().into_iter().collect(),
+            per_airport,
+            ranges: range_stats(res, config),
+            unserved_airports,
+            groups: group_stats(res, config, &empty_gates)?,
+        })
+    }
+    /// The statistics in `format`
+    pub fn format(&self, format: StatsFormat) -> Result<String> {
+        Ok(match format {
+            StatsFormat::Text => self.to_string(),
+            StatsFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+}
+
+/// How much of each range of flight numbers in `config` is used by `res`
+fn range_stats(res: &[Flight], config: &Config) -> Vec<RangeStats> {
+    let numbers = res.iter().map(|f| f.number).unique().collect::<Vec<_>>();
+    let mut ranges = vec![];
+    for pair in config.tier_pairs() {
+        ranges.extend(pair.range.iter().map(|r| (pair.name(), *r)));
+        for (a, airport_ranges) in &pair.range_per_airport {
+            ranges.extend(
+                airport_ranges
+                    .iter()
+                    .map(|r| (format!("{} {a}", pair.name()), *r)),
+            );
+        }
+    }
+    if config.multi_stop.enabled {
+        ranges.extend(
+            config
+                .multi_stop
+                .range
+                .iter()
+                .map(|r| ("multi_stop".to_owned(), *r)),
+        );
+    }
+    ranges
+        .into_iter()
+        .map(|(name, (start, end))| {
+            let used = numbers
+                .iter()
+                .filter(|n| (start..=end).contains(*n))
+                .count();
+            RangeStats {
+                name,
+                start,
+                end,
+                used,
+                used_percent: percent(used, usize::from(end.saturating_sub(start)) + 1),
+            }
+        })
+        .collect()
+}
+
+/// The statistics of each airport group
+fn group_stats(
+    res: &[Flight],
+    config: &mut Config,
+    empty_gates: &[Gate],
+) -> Result<BTreeMap<GroupName, GroupStats>> {
+    let gates = config.gates()?;
+    Ok(config
+        ._groups
         .iter()
-        .filter(|f| {
-            [
-                FlightType::ExistingH2H,
-                FlightType::ExistingH2N,
-                FlightType::ExistingN2N,
-            ]
-            .contains(&f.ty)
+        .map(|(name, airports)| {
+            let flights = res
+                .iter()
+                .filter(|f| f.stops().any(|(a, _)| airports.contains(a)))
+                .collect::<Vec<_>>();
+            let (n, duplicate) = duplicates(flights.iter().copied());
+            let stats = GroupStats {
+                airports: airports.len(),
+                gates: gates
+                    .iter()
+                    .filter(|g| airports.contains(&g.airport))
+                    .count(),
+                empty_gates: empty_gates
+                    .iter()
+                    .filter(|g| airports.contains(&g.airport))
+                    .count(),
+                flights: n,
+                flights_within: flights
+                    .iter()
+                    .filter(|f| f.stops().all(|(a, _)| airports.contains(a)))
+                    .count(),
+                duplicate_percent: percent(duplicate, n),
+            };
+            (name.clone(), stats)
         })
-        .count();
-    Ok(format!(
-        "==Flight Stats==\n\
-        Flights: {} ({} pairs)\n\
-        Destinations: {}\n\
-        Flight:Destination ratio: {:.2}\n\
-        Gates: {}\n\
-        Full gates: {}\n\
-        Empty gates: {}\n\
-        % duplicates: {:.2}\n\
-        ",
-        flights,
-        flight_pairs,
-        airports,
-        flight_pairs as f64 / airports as f64,
-        gates,
-        full_gates
-            .map(|f| f.to_string())
-            .sorted()
-            .collect::<Vec<_>>()
-            .join(", "),
-        empty_gates
-            .map(|f| f.to_string())
-            .sorted()
-            .collect::<Vec<_>>()
-            .join(", "),
-        duped_flights as f64 / flights as f64 * 100.0
-    ))
+        .collect())
+}
+
+/// The entries of `map` as `key: value`, separated by commas
+fn counts<K: Display, V: Display>(map: &BTreeMap<K, V>) -> String {
+    map.iter().map(|(k, v)| format!("{k}: {v}")).join(", ")
+}
+
+impl Display for PlanStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "==Flight Stats==")?;
+        writeln!(f, "Flights: {} ({} pairs)", self.flights, self.flight_pairs)?;
+        writeln!(f, "Destinations: {}", self.airports)?;
+        writeln!(
+            f,
+            "Flight:Destination ratio: {:.2}",
+            self.flight_pairs as f64 / self.airports as f64
+        )?;
+        writeln!(f, "Gates: {}", self.gates)?;
+        writeln!(f, "Full gates: {}", self.full_gates.join(", "))?;
+        writeln!(f, "Empty gates: {}", self.empty_gates.join(", "))?;
+        writeln!(f, "% duplicates: {:.2}", self.duplicate_percent)?;
+        writeln!(f, "Flights per type: {}", counts(&self.per_type))?;
+        writeln!(f, "Flights per hub: {}", counts(&self.per_hub))?;
+        writeln!(f, "Flights per size: {}", counts(&self.per_size))?;
+        writeln!(
+            f,
+            "Gates per number of destinations: {}",
+            counts(&self.dests_per_gate)
+        )?;
+
+        writeln!(f, "==Airport Stats==")?;
+        for (a, s) in &self.per_airport {
+            writeln!(
+                f,
+                "{a}: {} unique, {} duplicate ({:.2}% duplicates)",
+                s.unique, s.duplicate, s.duplicate_percent
+            )?;
+        }
+
+        if !self.ranges.is_empty() {
+            writeln!(f, "==Flight Number Ranges==")?;
+            for r in &self.ranges {
+                writeln!(
+                    f,
+                    "{} {}-{}: {} used ({:.2}%)",
+                    r.name, r.start, r.end, r.used, r.used_percent
+                )?;
+            }
+        }
+
+        if !self.unserved_airports.is_empty() {
+            writeln!(f, "==Unserved Airports==")?;
+            writeln!(
+                f,
+                "{}",
+                self.unserved_airports
+                    .iter()
+                    .map(|(a, n)| format!("{a} ({n} other flights)"))
+                    .join(", ")
+            )?;
+        }
+
+        if !self.groups.is_empty() {
+            writeln!(f, "==Group Stats==")?;
+            for (name, s) in &self.groups {
+                writeln!(
+                    f,
+                    "{name}: {} airports, {} gates ({} empty), {} flights ({} within the group), {:.2}% duplicates",
+                    s.airports, s.gates, s.empty_gates, s.flights, s.flights_within, s.duplicate_percent
+                )?;
+            }
+        }
+        Ok(())
+    }
 }
@@ -4,7 +4,10 @@ use anyhow::{anyhow, Result};
 use regex::Regex;
 
 use crate::{
-    types::{flight::Flight, flight_type::FlightType, fng::FlightNumberGenerator},
+    types::{
+        flight::Flight,
+        fng::{FlightNumberGenerator, FlightNumberGenerators},
+    },
     Config,
 };
 
@@ -19,20 +22,20 @@ pub fn update(
     let mut flight_number_mapping = HashMap::new();
     let mut new_flights = vec![];
 
-    let mut h2h_fng = FlightNumberGenerator::new(config.range_h2h.clone());
-    let mut h2n_fng = HashMap::new();
-    let mut n2n_fng = FlightNumberGenerator::new(config.range_n2n.clone());
+    let mut fngs = FlightNumberGenerators::default();
+    // multi-stop flights are numbered from their own range, as in `multi_stop::plan`
+    let mut multi_stop_fng = FlightNumberGenerator::new(config.multi_stop.range.clone());
 
     for flight in generated_plan {
-        if let Some(old_flight) = old_plan
-            .iter()
-            .find(|f| f.airport1 == flight.airport1 && f.airport2 == flight.airport2)
-        {
+        if let Some(old_flight) = old_plan.iter().find(|f| {
+            f.airport1 == flight.airport1 && f.airport2 == flight.airport2 && f.via == flight.via
+        }) {
             used_flight_numbers.push(old_flight.number.to_owned());
             new_plan.push(Flight {
                 number: old_flight.number,
                 airport1: flight.airport1,
                 airport2: flight.airport2,
+                via: flight.via,
                 size: flight.size,
                 score: flight.score,
                 ty: flight.ty,
@@ -43,49 +46,32 @@ pub fn update(
     }
 
     for flight in new_flights {
-        let flight_number = flight_number_mapping
-            .entry(flight.number)
-            .or_insert_with(|| {
-                let fng = match flight.ty {
-                    FlightType::ExistingH2H | FlightType::NonExistingH2H => &mut h2h_fng,
-                    FlightType::ExistingH2N | FlightType::NonExistingH2N => h2n_fng
-                        .entry(
-                            (if config
-                                .range_h2n
-                                .contains_key(&*flight.airport1.0.clone())
-                            {
-                                &flight.airport1.1
-                            } else {
-                                &flight.airport2.1
-                            })
-                            .to_owned(),
-                        )
-                        .or_insert_with(|| {
-                            FlightNumberGenerator::new(
-                                config
-                                    .range_h2n
-                                    .get(&*flight.airport1.0.clone())
-                                    .unwrap_or_else(|| {
-                                        &config.range_h2n[&*flight.airport2.0.clone()]
-                                    })
-                                    .to_owned(),
-                            )
-                        }),
-                    FlightType::ExistingN2N | FlightType::NonExistingN2N => &mut n2n_fng,
-                };
-
-                let mut fn_ = fng.next();
-                while used_flight_numbers.contains(&fn_.unwrap()) {
-                    fn_ = fng.next();
-                }
-                fn_.unwrap()
-            })
-            .to_owned();
+        let flight_number = if let Some(n) = flight_number_mapping.get(&flight.number) {
+            *n
+        } else {
+            let fng = if flight.via.is_empty() {
+                fngs.get(config, &flight.ty, &flight.airport1.0, &flight.airport2.0)?
+            } else {
+                &mut multi_stop_fng
+            };
+            let n = fng
+                .find(|a| !used_flight_numbers.contains(a))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Could not generate flight number for {} -> {}",
+                        flight.airport1.0,
+                        flight.airport2.0
+                    )
+                })?;
+            flight_number_mapping.insert(flight.number, n);
+            n
+        };
         used_flight_numbers.push(flight_number.to_owned());
         new_plan.push(Flight {
             number: flight_number,
             airport1: flight.airport1,
             airport2: flight.airport2,
+            via: flight.via,
             size: flight.size,
             score: flight.score,
             ty: flight.ty,
@@ -95,29 +81,45 @@ pub fn update(
 }
 
 pub fn load_from_out(out: PathBuf) -> Result<Vec<Flight>> {
-    let regex = Regex::new(r"(\d+) \((.*)\): (...) (.+) (...) (.+) \((\d+), (.2..)\)")?;
+    let regex = Regex::new(r"(\d+) \((.*)\): (...) (.+) (...) (.+) \((-?\d+), (\S+2\S+[ne])\)")?;
+    let via_regex = Regex::new("^(...) (.+)$")?;
     std::fs::read_to_string(out)?
         .split('\n')
         .filter(|l| !l.is_empty())
         .map(|l| {
             Some({
-                let re = regex.captures(l)?;
+                // the stops of multi-stop flights are split off first,
+                // so that the gate codes of the first and last stops are parsed as before
+                let (l, via) = match l.rsplit_once(" via ") {
+                    Some((head, tail)) => {
+                        let (via, rest) = tail.rsplit_once(" (")?;
+                        (format!("{head} ({rest}"), Some(via))
+                    }
+                    None => (l.to_owned(), None),
+                };
+                let re = regex.captures(&l)?;
 
                 Flight {
                     number: re.get(1)?.as_str().parse::<u16>().unwrap(),
                     airport1: (re.get(3)?.as_str().into(), re.get(4)?.as_str().into()),
                     airport2: (re.get(5)?.as_str().into(), re.get(6)?.as_str().into()),
+                    via: via.map_or_else(
+                        || Some(vec![]),
+                        |via| {
+                            via.split(", ")
+                                .map(|stop| {
+                                    let stop = via_regex.captures(stop)?;
+                                    Some((
+                                        stop.get(1)?.as_str().into(),
+                                        stop.get(2)?.as_str().into(),
+                                    ))
+                                })
+                                .collect()
+                        },
+                    )?,
                     size: re.get(2)?.as_str().into(),
                     score: re.get(7)?.as_str().parse::<i8>().unwrap(),
-                    ty: match re.get(8)?.as_str() {
-                        "H2Hn" => FlightType::NonExistingH2H,
-                        "H2Nn" => FlightType::NonExistingH2N,
-                        "N2Nn" => FlightType::NonExistingN2N,
-                        "H2He" => FlightType::ExistingH2H,
-                        "H2Ne" => FlightType::ExistingH2N,
-                        "N2Ne" => FlightType::ExistingN2N,
-                        _ => unreachable!(),
-                    },
+                    ty: re.get(8)?.as_str().parse().ok()?,
                 }
             })
         })
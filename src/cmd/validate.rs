@@ -0,0 +1,707 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fmt::Display,
+};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use log::{debug, warn};
+
+use crate::{
+    cmd::connectivity,
+    types::{
+        config::{Config, CONFIG_VERSION},
+        gate::Gate,
+        group::GROUP_PREFIX,
+        rule::{Effect, Route},
+        size, AirportCode, FlightNumber, Size,
+    },
+    FlightData,
+};
+
+/// The airport code used as a placeholder in the default config
+const PLACEHOLDER: &str = "XXX";
+
+/// A problem found in a config. Errors stop the planner from running, warnings do not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Error(String),
+    Warning(String),
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error(e) => write!(f, "error: {e}"),
+            Self::Warning(w) => write!(f, "warning: {w}"),
+        }
+    }
+}
+
+/// Checks the config before planning, logging the warnings and failing if there are errors
+pub fn require_valid(config: &mut Config, fd: &FlightData) -> Result<()> {
+    debug!("Checking config");
+    let mut errors = vec![];
+    for problem in validate(config, Some(fd))? {
+        match problem {
+            Problem::Warning(w) => warn!("{w}"),
+            Problem::Error(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+    Ok(())
+}
+
+/// Checks the config, returning every problem found.
+/// Checks that need the flight data are skipped if `fd` is `None`
+pub fn validate(config: &mut Config, fd: Option<&FlightData>) -> Result<Vec<Problem>> {
+    let mut problems = vec![];
+    if config.version < CONFIG_VERSION {
+        problems.push(Problem::Warning(format!(
+            "The config is in version {} of the config format (run `flight-network-planner migrate-config` to upgrade it to version {CONFIG_VERSION})",
+            config.version
+        )));
+    }
+    if config.airline_name.trim().is_empty() {
+        problems.push(Problem::Error(
+            "airline_name must be set to the name of your airline".into(),
+        ));
+    }
+    let gates = config.gates()?;
+    let airports = config.airports()?;
+
+    if let Some(fd) = fd {
+        for a in &airports {
+            if !fd.new_world_airports.contains(a) && !fd.old_world_airports.contains(a) {
+                problems.push(Problem::Warning(format!("Airport `{a}` doesn't exist")));
+            }
+        }
+    }
+    for a in config.hubs()? {
+        if !airports.contains(&a) {
+            problems.push(Problem::Warning(format!(
+                "Airport `{a}` has no gates but is stated as a hub"
+            )));
+        }
+    }
+
+    check_sizes(config, &gates, &mut problems);
+    check_multi_stop(config, &mut problems);
+    if check_tiers(config, &mut problems) {
+        check_ranges(config, fd, &gates, &mut problems)?;
+    }
+    check_airport_references(config, &airports, &mut problems);
+    check_gate_dests(config, &gates, &mut problems);
+    check_restrictions(config, &mut problems);
+    check_tag_rules(config, &gates, fd, &mut problems);
+    check_rules(config, &gates, &mut problems);
+    check_min_dests(config, &gates, &mut problems)?;
+    if config.connectivity.every_nonhub_connects_to_hub && connectivity::hubs(config)?.is_empty() {
+        problems.push(Problem::Error(
+            "connectivity.every_nonhub_connects_to_hub is set, but no airport is a hub".into(),
+        ));
+    }
+    Ok(problems)
+}
+
+fn check_sizes(config: &Config, gates: &[Gate], problems: &mut Vec<Problem>) {
+    if config.sizes.is_empty() {
+        let size_airports = gates
+            .iter()
+            .map(|g| (g.size.clone(), g.airport.clone()))
+            .into_group_map();
+        for (size, airports) in size_airports.iter().sorted() {
+            if airports.iter().all_equal() {
+                let hint = size::suggest(size_airports.keys(), size)
+                    .map(|k| format!(" (did you mean `{k}`?)"))
+                    .unwrap_or_default();
+                problems.push(Problem::Warning(format!(
+                    "Gate size `{size}` is only used at `{}`, so its gates will have no flights{hint}",
+                    airports[0]
+                )));
+            }
+        }
+    } else {
+        let known = config.sizes.iter().map(|s| &s.name).collect::<Vec<_>>();
+        let unknown = gates
+            .iter()
+            .map(|g| (g.size.clone(), format!("gate {} {}", g.airport, g.code)))
+            .chain(config.sizes.iter().flat_map(|s| {
+                s.fits
+                    .iter()
+                    .map(|f| (f.clone(), format!("`fits` of size {}", s.name)))
+            }))
+            .filter(|(size, _)| !known.contains(&size))
+            .map(|(size, place)| {
+                let hint = size::suggest(known.iter().copied(), &size)
+                    .map(|k| format!(" (did you mean `{k}`?)"))
+                    .unwrap_or_default();
+                format!("Unknown size `{size}` in {place}{hint}")
+            })
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            problems.push(Problem::Error(format!(
+                "{}\nKnown sizes are: {}",
+                unknown.join("\n"),
+                size::names(&config.sizes)
+            )));
+        }
+    }
+}
+
+fn check_multi_stop(config: &Config, problems: &mut Vec<Problem>) {
+    if !config.multi_stop.enabled {
+        return;
+    }
+    if config.multi_stop.max_legs < 2 {
+        problems.push(Problem::Error(
+            "multi_stop.max_legs must be at least 2".into(),
+        ));
+    }
+    if config.multi_stop.range.is_empty() {
+        problems.push(Problem::Error(
+            "Flight number range not specified for multi-stop flights".into(),
+        ));
+    }
+}
+
+/// Checks that the tiers and the pairs between them are complete.
+/// Returns whether they are, as the other checks on flight types rely on it
+fn check_tiers(config: &Config, problems: &mut Vec<Problem>) -> bool {
+    let before = problems.len();
+    let tiers = config.tiers();
+    if tiers.is_empty() {
+        problems.push(Problem::Error("At least one tier must be specified".into()));
+    }
+    for (i, tier) in tiers.iter().enumerate() {
+        if tier.name.is_empty() || !tier.name.chars().all(|c| c.is_ascii_alphabetic()) {
+            problems.push(Problem::Error(format!(
+                "Tier name `{}` must only contain letters",
+                tier.name
+            )));
+        }
+        if tiers[..i].iter().any(|t| t.name == tier.name) {
+            problems.push(Problem::Error(format!(
+                "Tier `{}` is specified more than once",
+                tier.name
+            )));
+        }
+    }
+    let tier_pairs = config.tier_pairs();
+    for pair in &tier_pairs {
+        for name in [&pair.tiers.0, &pair.tiers.1] {
+            if !tiers.iter().any(|t| t.name == *name) {
+                problems.push(Problem::Error(format!(
+                    "Unknown tier `{name}` in tier pair {}2{}",
+                    pair.tiers.0, pair.tiers.1
+                )));
+            }
+        }
+    }
+    let pairs_not_specified = tiers
+        .iter()
+        .tuple_combinations()
+        .chain(tiers.iter().map(|t| (t, t)))
+        .filter(|(t1, t2)| !tier_pairs.iter().any(|p| p.matches(&t1.name, &t2.name)))
+        .map(|(t1, t2)| format!("{}2{}", t1.name, t2.name))
+        .collect::<Vec<_>>();
+    if !pairs_not_specified.is_empty() {
+        problems.push(Problem::Error(format!(
+            "Tier pair not specified for: {}",
+            pairs_not_specified.join(", ")
+        )));
+    }
+    problems.len() == before
+}
+
+fn range_size(range: &[(FlightNumber, FlightNumber)]) -> usize {
+    range
+        .iter()
+        .map(|(a, b)| usize::from(b.saturating_sub(*a)) + usize::from(a <= b))
+        .sum()
+}
+
+/// Checks that every flight type has a flight number range for every airport,
+/// and that the ranges don't overlap
+fn check_ranges(
+    config: &mut Config,
+    fd: Option<&FlightData>,
+    gates: &[Gate],
+    problems: &mut Vec<Problem>,
+) -> Result<()> {
+    let tiers = config.tiers();
+    let tier_pairs = config.tier_pairs();
+    let gate_tiers = gate_tiers(config, gates)?;
+
+    let fnr_not_specified = tier_pairs
+        .iter()
+        .filter(|p| p.range.is_empty())
+        .flat_map(|p| {
+            gate_tiers
+                .iter()
+                .filter(|((a, _), t)| {
+                    tiers[**t].name == p.tiers.0 && !p.range_per_airport.contains_key(a)
+                })
+                .map(|((a, _), _)| a.clone())
+        })
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    if !fnr_not_specified.is_empty() {
+        problems.push(Problem::Error(format!(
+            "Flight number range not specified for: {}",
+            fnr_not_specified.join(", ")
+        )));
+    }
+
+    let mut ranges = vec![];
+    for pair in &tier_pairs {
+        let name = pair.name();
+        ranges.push((format!("{name} flights"), &pair.range));
+        for (a, range) in &pair.range_per_airport {
+            ranges.push((format!("{name} flights at {a}"), range));
+        }
+    }
+    if config.multi_stop.enabled {
+        ranges.push(("multi-stop flights".into(), &config.multi_stop.range));
+    }
+    for (name, range) in &ranges {
+        for (a, b) in *range {
+            if a > b {
+                problems.push(Problem::Warning(format!(
+                    "Flight number range [{a}, {b}] for {name} is empty"
+                )));
+            }
+        }
+        // each range in a list is walked through in turn, so overlapping ones repeat numbers
+        for ((a1, b1), (a2, b2)) in range.iter().tuple_combinations() {
+            if a1 <= b2 && a2 <= b1 {
+                problems.push(Problem::Error(format!(
+                    "Flight number ranges [{a1}, {b1}] and [{a2}, {b2}] for {name} overlap ({}-{})",
+                    a1.max(a2),
+                    b1.min(b2)
+                )));
+            }
+        }
+    }
+    // the flights of each range are numbered separately, so overlapping ranges give
+    // different flights the same number
+    for ((name1, range1), (name2, range2)) in ranges.iter().tuple_combinations() {
+        for ((a1, b1), (a2, b2)) in range1.iter().cartesian_product(range2.iter()) {
+            if a1 <= b2 && a2 <= b1 {
+                problems.push(Problem::Error(format!(
+                    "Flight number ranges for {name1} and {name2} overlap ({}-{})",
+                    a1.max(a2),
+                    b1.min(b2)
+                )));
+            }
+        }
+    }
+
+    check_range_sizes(config, fd, gates, problems)
+}
+
+/// The number of flight numbers that each flight number range may need,
+/// keyed by the name of the tier pair and the airport if the range is per airport.
+/// Each pair of airports has at most one flight, so this is the number of airport pairs
+/// that could have a flight using the range, going by the rules as `run` does.
+/// Without `fd`, routes are taken not to be flown by other airlines, and rules requiring
+/// a maximum distance forbid every route, as distances are not known
+pub fn needed_numbers(
+    config: &mut Config,
+    fd: Option<&FlightData>,
+    gates: &[Gate],
+) -> Result<BTreeMap<(String, Option<AirportCode>), usize>> {
+    let gate_tiers = gate_tiers(config, gates)?;
+    let mut possible_pairs: BTreeMap<_, Vec<(AirportCode, AirportCode)>> = BTreeMap::new();
+    for (g1, g2) in gates.iter().tuple_combinations() {
+        if g1.airport == g2.airport || size::plane_size(&config.sizes, g1, g2).is_none() {
+            continue;
+        }
+        let route = match fd {
+            Some(fd) => Route::between_gates(config, fd, g1, g2)?,
+            None => Route::between_gates_without_data(config, g1, g2)?,
+        };
+        if config.judge(&route).forbidden_by.is_some() {
+            continue;
+        }
+        let t1 = gate_tiers[&(g1.airport.clone(), g1.size.clone())];
+        let t2 = gate_tiers[&(g2.airport.clone(), g2.size.clone())];
+        let (g1, g2) = if t2 < t1 { (g2, g1) } else { (g1, g2) };
+        let ty = config.flight_type(t1, t2, false);
+        let Some(pair) = config.tier_pair(&ty) else {
+            continue;
+        };
+        let airport = [&g1.airport, &g2.airport]
+            .into_iter()
+            .find(|a| pair.range_per_airport.contains_key(*a))
+            .cloned();
+        let airports = if g1.airport < g2.airport {
+            (g1.airport.clone(), g2.airport.clone())
+        } else {
+            (g2.airport.clone(), g1.airport.clone())
+        };
+        let entry = possible_pairs.entry((pair.name(), airport)).or_default();
+        if !entry.contains(&airports) {
+            entry.push(airports);
+        }
+    }
+    let per_pair = if config.both_dir_same_num { 1 } else { 2 };
+    Ok(possible_pairs
+        .into_iter()
+        .map(|(k, airports)| (k, airports.len() * per_pair))
+        .collect())
+}
+
+/// Checks that the flight number ranges are large enough for every flight that could be planned
+fn check_range_sizes(
+    config: &mut Config,
+    fd: Option<&FlightData>,
+    gates: &[Gate],
+    problems: &mut Vec<Problem>,
+) -> Result<()> {
+    let tier_pairs = config.tier_pairs();
+    for ((name, airport), needed) in needed_numbers(config, fd, gates)? {
+        let Some(pair) = tier_pairs.iter().find(|p| p.name() == name) else {
+            continue;
+        };
+        let (range, label) = airport.as_ref().map_or_else(
+            || (&pair.range, format!("{name} flights")),
+            |a| (&pair.range_per_airport[a], format!("{name} flights at {a}")),
+        );
+        let available = range_size(range);
+        if available > 0 && available < needed {
+            problems.push(Problem::Warning(format!(
+                "Flight number range for {label} has {available} numbers, but up to {needed} may be needed"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The index of the tier of each airport and gate size
+fn gate_tiers(config: &mut Config, gates: &[Gate]) -> Result<BTreeMap<(AirportCode, Size), usize>> {
+    let mut gate_tiers = BTreeMap::new();
+    for g in gates {
+        if let Entry::Vacant(e) = gate_tiers.entry((g.airport.clone(), g.size.clone())) {
+            e.insert(config.tier(&g.airport, &g.size)?);
+        }
+    }
+    Ok(gate_tiers)
+}
+
+/// Whether flights between the two airports are restricted by `restricted_to`
+fn restricted_to(config: &Config, a1: &AirportCode, a2: &AirportCode) -> bool {
+    config.restricted_to.get(a1).is_some_and(|r| r.contains(a2))
+        || config.restricted_to.get(a2).is_some_and(|r| r.contains(a1))
+}
+
+/// Checks airports mentioned in the restrictions and limits,
+/// for placeholders left from the default config and for airports without gates
+fn check_airport_references(
+    config: &Config,
+    airports: &[AirportCode],
+    problems: &mut Vec<Problem>,
+) {
+    let mut references: Vec<(&AirportCode, &str)> = vec![];
+    references.extend(
+        config
+            .groups
+            .values()
+            .flatten()
+            .filter(|a| !a.starts_with(GROUP_PREFIX))
+            .map(|a| (a, "groups")),
+    );
+    references.extend(config.gates.keys().map(|a| (a, "gates")));
+    references.extend(
+        config
+            .restricted_between
+            .iter()
+            .flatten()
+            .map(|a| (a, "restricted_between")),
+    );
+    for (a, dests) in &config.restricted_to {
+        references.extend(
+            std::iter::once(a)
+                .chain(dests)
+                .map(|a| (a, "restricted_to")),
+        );
+    }
+    references.extend(
+        config
+            .preferred_between
+            .iter()
+            .flatten()
+            .map(|a| (a, "preferred_between")),
+    );
+    for (a, dests) in &config.preferred_to {
+        references.extend(std::iter::once(a).chain(dests).map(|a| (a, "preferred_to")));
+    }
+    references.extend(config.no_dupes.iter().map(|a| (a, "no_dupes")));
+    for (place, map) in [
+        ("gate_allowed_dests", &config.gate_allowed_dests),
+        ("gate_denied_dests", &config.gate_denied_dests),
+    ] {
+        for (a, gates) in map {
+            references.extend(
+                std::iter::once(a)
+                    .chain(gates.values().flatten())
+                    .map(|a| (a, place)),
+            );
+        }
+    }
+    references.extend(
+        config
+            .tag_rules
+            .iter()
+            .flat_map(|r| [&r.allowed_dests, &r.denied_dests, &r.preferred_dests])
+            .flatten()
+            .map(|a| (a, "tag_rules")),
+    );
+    references.extend(
+        config
+            .rules
+            .iter()
+            .flat_map(|r| [&r.from.airports, &r.to.airports])
+            .flatten()
+            .filter(|a| !a.starts_with(GROUP_PREFIX))
+            .map(|a| (a, "rules")),
+    );
+    references.extend(
+        config
+            .max_dests_per_gate
+            .keys()
+            .map(|a| (a, "max_dests_per_gate")),
+    );
+    references.extend(config.min_dests.keys().map(|a| (a, "min_dests")));
+    references.extend(config.range_h2n.keys().map(|a| (a, "range_h2n")));
+    // without tiers, the ranges per airport of the tier pairs are the ones in `range_h2n`
+    let tier_pairs = config.tier_pairs();
+    references.extend(
+        tier_pairs
+            .iter()
+            .flat_map(|p| p.range_per_airport.keys())
+            .filter(|a| !config.range_h2n.contains_key(*a))
+            .map(|a| (a, "tier_pairs")),
+    );
+    references.extend(
+        config
+            .min_dests_per_gate
+            .keys()
+            .map(|a| (a, "min_dests_per_gate")),
+    );
+
+    for (a, place) in references.into_iter().sorted().dedup() {
+        if *a == PLACEHOLDER {
+            problems.push(Problem::Warning(format!(
+                "Placeholder airport `{a}` from the default config is still in {place}"
+            )));
+        } else if place != "gates" && !airports.contains(a) {
+            problems.push(Problem::Warning(format!(
+                "Airport `{a}` in {place} has no gates"
+            )));
+        }
+    }
+}
+
+fn check_gate_dests(config: &Config, gates: &[Gate], problems: &mut Vec<Problem>) {
+    for (place, map) in [
+        ("gate_allowed_dests", &config.gate_allowed_dests),
+        ("gate_denied_dests", &config.gate_denied_dests),
+    ] {
+        for (a, codes) in map {
+            if *a == PLACEHOLDER {
+                continue;
+            }
+            for code in codes.keys() {
+                if !gates.iter().any(|g| g.airport == *a && g.code == *code) {
+                    problems.push(Problem::Warning(format!(
+                        "Gate `{a} {code}` in {place} doesn't exist"
+                    )));
+                }
+            }
+        }
+    }
+}
+
+fn check_restrictions(config: &Config, problems: &mut Vec<Problem>) {
+    for (place, sets) in [
+        ("restricted_between", &config.restricted_between),
+        ("preferred_between", &config.preferred_between),
+    ] {
+        for set in sets {
+            if set.iter().sorted().dedup().count() < 2 {
+                problems.push(Problem::Warning(format!(
+                    "Set [{}] in {place} has fewer than two airports, so it has no effect",
+                    set.join(", ")
+                )));
+            }
+        }
+    }
+
+    let preferred = config
+        .preferred_between
+        .iter()
+        .flat_map(|set| {
+            set.iter()
+                .tuple_combinations()
+                .map(|(a, b)| (a, b, "preferred_between"))
+        })
+        .chain(
+            config
+                .preferred_to
+                .iter()
+                .flat_map(|(a, dests)| dests.iter().map(move |b| (a, b, "preferred_to"))),
+        )
+        .filter(|(a, b, _)| a != b && **a != PLACEHOLDER && **b != PLACEHOLDER)
+        .sorted()
+        .dedup();
+    for (a, b, place) in preferred {
+        if config
+            .restricted_between
+            .iter()
+            .any(|re| re.contains(a) && re.contains(b))
+        {
+            problems.push(Problem::Warning(format!(
+                "Flights between `{a}` and `{b}` are in {place} but also in restricted_between"
+            )));
+        }
+        if restricted_to(config, a, b) {
+            problems.push(Problem::Warning(format!(
+                "Flights between `{a}` and `{b}` are in {place} but also in restricted_to"
+            )));
+        }
+    }
+}
+
+fn check_tag_rules(
+    config: &Config,
+    gates: &[Gate],
+    fd: Option<&FlightData>,
+    problems: &mut Vec<Problem>,
+) {
+    let tiers = config.tiers();
+    for rule in &config.tag_rules {
+        let tags = std::iter::once((&rule.tag, "tag"))
+            .chain(rule.allowed_tags.iter().map(|t| (t, "allowed_tags")))
+            .chain(rule.denied_tags.iter().map(|t| (t, "denied_tags")))
+            .chain(rule.preferred_tags.iter().map(|t| (t, "preferred_tags")));
+        for (tag, place) in tags {
+            if !gates.iter().any(|g| g.tags.contains(tag)) {
+                problems.push(Problem::Warning(format!(
+                    "No gate has the tag `{tag}` in the {place} of tag rule `{}`",
+                    rule.tag
+                )));
+            }
+        }
+        for tier in &rule.preferred_tiers {
+            if !tiers.iter().any(|t| t.name == *tier) {
+                problems.push(Problem::Error(format!(
+                    "Tier `{tier}` in the preferred_tiers of tag rule `{}` doesn't exist",
+                    rule.tag
+                )));
+            }
+        }
+    }
+
+    let Some(fd) = fd else {
+        return;
+    };
+    if let Some(rule) = config.tag_rules.iter().find(|r| r.max_distance.is_some()) {
+        for a in gates.iter().map(|g| &g.airport).sorted().dedup() {
+            if !fd.coordinates.contains_key(a) {
+                problems.push(Problem::Warning(format!(
+                    "Airport `{a}` has no coordinates in Gatelogue, so gates tagged `{}` cannot fly to it as it has a max_distance",
+                    rule.tag
+                )));
+            }
+        }
+    }
+}
+
+fn check_rules(config: &Config, gates: &[Gate], problems: &mut Vec<Problem>) {
+    let tiers = config.tiers();
+    let pairs = config.tier_pairs();
+    for (i, rule) in config.rules.iter().enumerate() {
+        let name = rule
+            .name
+            .as_ref()
+            .map_or_else(|| format!("rules[{i}]"), |n| format!("rule `{n}`"));
+        for end in [&rule.from, &rule.to] {
+            for tier in &end.tiers {
+                if !tiers.iter().any(|t| t.name == *tier) {
+                    problems.push(Problem::Error(format!(
+                        "Tier `{tier}` in {name} doesn't exist"
+                    )));
+                }
+            }
+            for tag in &end.tags {
+                if !gates.iter().any(|g| g.tags.contains(tag)) {
+                    problems.push(Problem::Warning(format!(
+                        "No gate has the tag `{tag}` in {name}"
+                    )));
+                }
+            }
+            for code in &end.gates {
+                if !gates.iter().any(|g| {
+                    g.code == *code
+                        && (end.airports.is_empty() || end.airports.contains(&g.airport))
+                }) {
+                    problems.push(Problem::Warning(format!(
+                        "Gate `{code}` in {name} doesn't exist at its airports"
+                    )));
+                }
+            }
+        }
+        for ty in &rule.types {
+            let known = std::iter::once(ty.as_str())
+                .chain(ty.strip_suffix(['e', 'n']))
+                .filter_map(|pair| pair.split_once('2'))
+                .any(|(t1, t2)| pairs.iter().any(|p| p.matches(&t1.into(), &t2.into())));
+            if !known {
+                problems.push(Problem::Error(format!(
+                    "Flight type `{ty}` in {name} is not a pair of tiers in tier_pairs"
+                )));
+            }
+        }
+        match (rule.effect, rule.score) {
+            (None, None) => problems.push(Problem::Error(format!(
+                "Neither an effect nor a score is given in {name}"
+            ))),
+            (Some(Effect::Require), _) if rule.to.is_empty() => {
+                problems.push(Problem::Warning(format!(
+                    "The `to` of {name} is empty, so requiring it has no effect"
+                )));
+            }
+            (None, Some(_)) if rule.unless_preferred => problems.push(Problem::Warning(format!(
+                "unless_preferred has no effect in {name}, as it only applies to an effect"
+            ))),
+            _ => {}
+        }
+    }
+}
+
+fn check_min_dests(config: &mut Config, gates: &[Gate], problems: &mut Vec<Problem>) -> Result<()> {
+    for g in gates {
+        let (min, max) = (config.min_dests(g), config.hard_max(g)?);
+        if min > max {
+            problems.push(Problem::Warning(format!(
+                "Gate `{} {}` has a min_dests of {min}, more than its maximum of {max} destinations",
+                g.airport, g.code
+            )));
+        }
+    }
+    for (a, min) in config.min_dests.clone() {
+        let mut max = 0usize;
+        for g in gates.iter().filter(|g| g.airport == a) {
+            max = max.saturating_add(config.hard_max(g)?);
+        }
+        if usize::from(min) > max {
+            problems.push(Problem::Warning(format!(
+                "Airport `{a}` has a min_dests of {min}, more than its gates can have ({max} destinations)"
+            )));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    cmd::run,
+    types::{
+        config::Config, flight::Flight, flight_data::FlightData, gate::Gate, size, AirportCode,
+        GateCode, Size,
+    },
+};
+
+/// Hypothetical gates to buy at an airport
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub airport: AirportCode,
+    pub size: Size,
+    pub count: u8,
+}
+
+impl FromStr for Candidate {
+    type Err = anyhow::Error;
+
+    /// Parses `<airport>:<size>` or `<airport>:<size>:<count>`
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.split(':');
+        let (Some(airport), Some(size)) = (parts.next(), parts.next()) else {
+            return Err(anyhow!(
+                "`{s}` should be `<airport>:<size>` or `<airport>:<size>:<count>`"
+            ));
+        };
+        let count = parts.next().map_or(Ok(1), str::parse)?;
+        if parts.next().is_some() || count == 0 {
+            return Err(anyhow!(
+                "`{s}` should be `<airport>:<size>` or `<airport>:<size>:<count>`, with a count of at least 1"
+            ));
+        }
+        Ok(Self {
+            airport: airport.into(),
+            size: size.into(),
+            count,
+        })
+    }
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}x{}", self.airport, self.count, self.size)
+    }
+}
+
+impl Candidate {
+    /// The gates of the candidate, with codes that no gate at the airport has
+    fn gates(&self, existing: &[Gate]) -> Vec<Gate> {
+        (1..)
+            .map(|i| GateCode::from(format!("?{i}")))
+            .filter(|code| {
+                !existing
+                    .iter()
+                    .any(|g| g.airport == self.airport && g.code == *code)
+            })
+            .take(usize::from(self.count))
+            .map(|code| Gate {
+                airport: self.airport.clone(),
+                code,
+                size: self.size.clone(),
+                tags: vec![],
+                max_dests: None,
+                min_dests: None,
+                note: None,
+            })
+            .collect()
+    }
+}
+
+/// The numbers of a plan that candidates are compared by
+#[derive(Debug, Clone, Copy)]
+pub struct Totals {
+    pub flights: usize,
+    /// Flights that no other airline flies
+    pub unique: usize,
+    /// The sum of the scores of the flights
+    pub score: i64,
+}
+
+impl Totals {
+    fn new(plan: &[Flight]) -> Self {
+        Self {
+            flights: plan.len(),
+            unique: plan.iter().filter(|f| !f.ty.existing).count(),
+            score: plan.iter().map(|f| i64::from(f.score)).sum(),
+        }
+    }
+}
+
+/// The plan with a candidate's gates, or why it could not be made
+pub struct Outcome {
+    pub candidate: Candidate,
+    pub totals: Result<Totals, String>,
+}
+
+/// The candidates ranked by how much they improve on the plan without them
+pub struct WhatIf {
+    pub baseline: Totals,
+    /// The candidates, with the largest gain in unique flights and then in score first.
+    /// Candidates that could not be planned are last
+    pub outcomes: Vec<Outcome>,
+}
+
+/// Plans with the gates of each of `candidates` added to `config`, one candidate at a time
+pub fn what_if(
+    config: &Config,
+    fd: &FlightData,
+    candidates: &[Candidate],
+    seed: Option<u64>,
+) -> Result<WhatIf> {
+    let gates = config.clone().gates()?;
+    for c in candidates {
+        if !config.sizes.is_empty() && !config.sizes.iter().any(|s| s.name == c.size) {
+            return Err(anyhow!(
+                "Unknown size `{}` in candidate {c}\nKnown sizes are: {}",
+                c.size,
+                size::names(&config.sizes)
+            ));
+        }
+        if !fd.old_world_airports.contains(&c.airport)
+            && !fd.new_world_airports.contains(&c.airport)
+            && !gates.iter().any(|g| g.airport == c.airport)
+        {
+            return Err(anyhow!(
+                "Airport `{}` in candidate {c} is not in Gatelogue",
+                c.airport
+            ));
+        }
+    }
+
+    info!("Planning without any candidates");
+    let baseline = Totals::new(&run::run(&mut config.clone(), fd, None, seed)?);
+    let mut outcomes = vec![];
+    for candidate in candidates {
+        info!("Planning with {candidate}");
+        let mut config = config.clone();
+        config.add_gates(candidate.gates(&gates))?;
+        outcomes.push(Outcome {
+            candidate: candidate.clone(),
+            totals: run::run(&mut config, fd, None, seed)
+                .map(|plan| Totals::new(&plan))
+                .map_err(|e| e.to_string()),
+        });
+    }
+    let outcomes = outcomes
+        .into_iter()
+        .sorted_by_key(|o| std::cmp::Reverse(o.totals.as_ref().ok().map(|t| (t.unique, t.score))))
+        .collect();
+    Ok(WhatIf { baseline, outcomes })
+}
+
+/// The change from `old` to `new`, with its sign
+fn change(new: usize, old: usize) -> String {
+    if new >= old {
+        format!("+{}", new - old)
+    } else {
+        format!("-{}", old - new)
+    }
+}
+
+impl Display for WhatIf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let b = self.baseline;
+        writeln!(f, "==Baseline==")?;
+        writeln!(
+            f,
+            "{} flights ({} unique), score {}",
+            b.flights, b.unique, b.score
+        )?;
+        writeln!(f, "==Candidates==")?;
+        for (i, o) in self.outcomes.iter().enumerate() {
+            match &o.totals {
+                Ok(t) => writeln!(
+                    f,
+                    "{}. {}: {} unique flights, {} flights, {:+} score",
+                    i + 1,
+                    o.candidate,
+                    change(t.unique, b.unique),
+                    change(t.flights, b.flights),
+                    t.score - b.score
+                )?,
+                Err(e) => writeln!(f, "{}. {}: could not be planned: {e}", i + 1, o.candidate)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::types::{config::WikiConfig, flight::Flight};
+
+/// `template` with each `{name}` replaced by its value in `values`, in one pass so that
+/// placeholders in the values are not replaced. Unknown placeholders are left as they are
+fn fill(template: &str, values: &[(&str, String)]) -> String {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some((before, after)) = rest.split_once('{') {
+        filled.push_str(before);
+        let placeholder = after.split_once('}').and_then(|(name, tail)| {
+            let (_, value) = values.iter().find(|(n, _)| *n == name)?;
+            Some((value, tail))
+        });
+        if let Some((value, tail)) = placeholder {
+            filled.push_str(value);
+            rest = tail;
+        } else {
+            filled.push('{');
+            rest = after;
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// A wikitable with `class`, `header` and `rows`
+fn table<I: Iterator<Item = String>>(class: &str, header: &str, rows: I) -> String {
+    let mut lines = vec![format!(r#"{{| class="{class}""#), header.to_owned()];
+    for row in rows {
+        lines.push("|-".to_owned());
+        lines.push(row);
+    }
+    lines.push("|}".to_owned());
+    lines.join("\n")
+}
+
+/// The table of `flights`, followed by the departure board of each airport
+pub fn wiki(flights: &[Flight], templates: &WikiConfig) -> String {
+    let flight_rows = flights.iter().sorted_by_key(|f| f.number).map(|f| {
+        fill(
+            &templates.flight_row,
+            &[
+                ("number", f.number.to_string()),
+                ("from", f.airport1.0.to_string()),
+                ("from_gate", f.airport1.1.to_string()),
+                ("to", f.airport2.0.to_string()),
+                ("to_gate", f.airport2.1.to_string()),
+                (
+                    "via",
+                    f.via.iter().map(|(a, g)| format!("{a} {g}")).join(", "),
+                ),
+                ("size", f.size.to_string()),
+                ("type", f.ty.to_string()),
+            ],
+        )
+    });
+    let mut sections = vec![table(
+        "wikitable sortable",
+        &templates.flight_header,
+        flight_rows,
+    )];
+
+    // every leg of a multi-stop flight departs from its gate, as in `gate-keys`
+    let mut boards = BTreeMap::<_, BTreeMap<_, Vec<_>>>::new();
+    for flight in flights {
+        for (from, to) in flight.legs() {
+            boards
+                .entry(&from.0)
+                .or_default()
+                .entry(&from.1)
+                .or_default()
+                .push((flight, to));
+        }
+    }
+    for (airport, gates) in boards {
+        let mut rows = vec![];
+        for (gate, departures) in gates {
+            rows.push(fill(
+                &templates.board_gate,
+                &[("airport", airport.to_string()), ("gate", gate.to_string())],
+            ));
+            rows.extend(
+                departures
+                    .into_iter()
+                    .sorted_by_key(|(f, to)| (f.number, *to))
+                    .map(|(f, to)| {
+                        fill(
+                            &templates.board_row,
+                            &[
+                                ("number", f.number.to_string()),
+                                ("gate", gate.to_string()),
+                                ("to", to.0.to_string()),
+                                ("to_gate", to.1.to_string()),
+                                ("size", f.size.to_string()),
+                                ("type", f.ty.to_string()),
+                            ],
+                        )
+                    }),
+            );
+        }
+        sections.push(format!(
+            "{}\n{}",
+            fill(
+                &templates.board_heading,
+                &[("airport", airport.to_string())]
+            ),
+            // sorting would break up the departures of each gate
+            table("wikitable", &templates.board_header, rows.into_iter())
+        ));
+    }
+    sections.join("\n\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_replaces_each_placeholder_once() {
+        let values = [("from", "{to}".to_owned()), ("to", "BBB".to_owned())];
+        assert_eq!(fill("{from} -> {to}", &values), "{to} -> BBB");
+    }
+
+    #[test]
+    fn fill_keeps_unknown_placeholders_and_wiki_markup() {
+        let values = [("number", "1".to_owned())];
+        assert_eq!(
+            fill(r#"{| class="x" {{tl}} {unknown} {number}"#, &values),
+            r#"{| class="x" {{tl}} {unknown} 1"#
+        );
+    }
+}
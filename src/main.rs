@@ -2,17 +2,30 @@ mod cmd;
 mod types;
 mod utils;
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::BTreeMap, path::PathBuf};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::{CommandFactory, Parser};
 use clap_complete_command::Shell;
 use itertools::Itertools;
-use types::config::Config;
+use types::{
+    config::{Config, WikiConfig},
+    AirlineName, AirportCode,
+};
 
 use crate::{
-    cmd::{run, stats, update},
-    types::flight_data::FlightData,
+    cmd::{
+        analyze,
+        export::{self, ExportFormat},
+        history, impact,
+        init::{self, InitOptions},
+        migrate, route, run, schema,
+        stats::{PlanStats, StatsFormat},
+        update, validate,
+        what_if::{self, Candidate},
+        wiki,
+    },
+    types::{flight_data::FlightData, group},
 };
 
 #[derive(Parser)]
@@ -25,10 +38,33 @@ struct Args {
 enum Command {
     /// Run the planner
     Run(Run),
+    /// Check a configuration file and report every problem found
+    Validate(Validate),
+    /// Report how a flight plan covers the air network, to find where to buy gates next
+    Analyze(Analyze),
+    /// Compare the plans with hypothetical extra gates, to find which gates are worth buying
+    WhatIf(WhatIf),
+    /// Report the flights of an old plan that other airlines have started or stopped flying,
+    /// and suggest replacements for the gates of the new duplicates
+    Impact(Impact),
+    /// Report how other airlines' flights and your plans changed over the saved snapshots
+    History(History),
+    /// Find the best itineraries between two airports on your airline's flights
+    Route(Route),
+    /// Export the plan as a graph or a map, for drawing route maps
+    Export(Export),
     /// Gets the configuration for the planner
     GetConfig,
+    /// Make a starter configuration file for your airline by answering some questions
+    Init(Init),
+    /// Upgrade a configuration file to the current version of the config format
+    MigrateConfig(MigrateConfig),
+    /// Gets the JSON Schema of the configuration, for editors with YAML language servers
+    Schema(Schema),
     /// Tool to format the output of `run` as a mapping of gates to destinations
     GateKeys(GateKeys),
+    /// Tool to format the output of `run` as wiki tables of flights and departure boards
+    Wiki(Wiki),
     /// Generate a completion file for your shell
     Completion {
         /// The shell to generate for
@@ -44,6 +80,9 @@ struct Run {
     /// Whether to print statistics
     #[clap(short, long, action)]
     stats: bool,
+    /// The format of the statistics
+    #[clap(long, value_enum, default_value_t)]
+    stats_format: StatsFormat,
     /// The old output file
     /// (will be used to preserve original flight routes so it won't duplicate so much)
     #[clap(short, long, value_parser)]
@@ -51,6 +90,143 @@ struct Run {
     /// Whether to replace the old file instead of printing to stdout
     #[clap(short, long, action)]
     replace: bool,
+    /// Seed for breaking ties between equally-scored flights in a pseudo-random order
+    /// (without a seed, ties are broken by airport and gate code)
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Parser)]
+struct Validate {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// Skip the checks that need flight data from Gatelogue
+    #[clap(long, action)]
+    offline: bool,
+}
+
+#[derive(Parser)]
+struct Analyze {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// The output file of `run` to analyse (default: run the planner)
+    #[clap(short, long)]
+    plan: Option<PathBuf>,
+    /// The number of underserved pairs of airports to list
+    #[clap(long, default_value_t = 20)]
+    top: usize,
+}
+
+#[derive(Parser)]
+struct WhatIf {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// Gates to try adding, as `<airport>:<size>` or `<airport>:<size>:<count>`.
+    /// Each candidate is tried on its own
+    #[clap(required = true)]
+    candidates: Vec<Candidate>,
+    /// Seed for breaking ties between equally-scored flights, as in `run`
+    #[clap(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Parser)]
+struct Impact {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// The output file of an earlier `run`
+    #[clap(default_value = "out.txt")]
+    out_file: PathBuf,
+}
+
+#[derive(Parser)]
+struct History {
+    /// The configuration YML file to read `history_dir` from
+    file: PathBuf,
+    /// Save a snapshot of the current flight data first (needs the internet)
+    #[clap(long, action)]
+    snapshot: bool,
+}
+
+#[derive(Parser)]
+struct Route {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// The airport to start from
+    from: AirportCode,
+    /// The airport to go to
+    to: AirportCode,
+    /// The output file of `run` to use (default: run the planner)
+    #[clap(short, long)]
+    plan: Option<PathBuf>,
+    /// Also use other airlines' flights from Gatelogue
+    #[clap(short, long, action)]
+    competitors: bool,
+    /// The most transfers an itinerary can have
+    #[clap(long, default_value_t = 2)]
+    max_transfers: usize,
+    /// The number of itineraries to list
+    #[clap(short = 'n', long, default_value_t = 5)]
+    limit: usize,
+}
+
+#[derive(Parser)]
+struct Export {
+    /// The configuration YML file to read from
+    file: PathBuf,
+    /// The format to export to
+    #[arg(value_enum)]
+    format: ExportFormat,
+    /// The output file of `run` to export (default: run the planner)
+    #[clap(short, long)]
+    plan: Option<PathBuf>,
+    /// The file to write to (default: print to stdout)
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+struct Init {
+    /// The file to write the configuration to
+    #[clap(default_value = "config.yml")]
+    output: PathBuf,
+    /// The name of the airline
+    #[clap(long)]
+    airline_name: Option<AirlineName>,
+    /// The gate file, relative to the folder of the configuration file
+    #[clap(long)]
+    gate_file: Option<PathBuf>,
+    /// The hubs, separated by commas (default: the airports with at least `hub-threshold` gates)
+    #[clap(long, value_delimiter = ',')]
+    hubs: Option<Vec<AirportCode>>,
+    /// The minimum number of gates for an airport to be suggested as a hub
+    #[clap(long)]
+    hub_threshold: Option<usize>,
+    /// Don't ask any questions, using the flags and defaults instead
+    #[clap(long, action)]
+    non_interactive: bool,
+    /// Whether to overwrite the file if it exists
+    #[clap(short, long, action)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct MigrateConfig {
+    /// The configuration YML file to upgrade
+    file: PathBuf,
+    /// The file to write the upgraded configuration to
+    /// (default: replace the file, keeping the original as `<file>.bak`)
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+    /// Only print the changes, without writing anything
+    #[clap(long, action)]
+    dry_run: bool,
+}
+
+#[derive(Parser)]
+struct Schema {
+    /// A configuration YML file whose `sizes` the gate sizes in the schema are limited to
+    config: Option<PathBuf>,
 }
 
 #[derive(Parser)]
@@ -60,23 +236,35 @@ struct GateKeys {
     out_file: PathBuf,
 }
 
+#[derive(Parser)]
+struct Wiki {
+    /// The output file from `run`
+    #[clap(default_value = "out.txt")]
+    out_file: PathBuf,
+    /// A configuration YML file to read the templates in `wiki` from
+    #[clap(short, long)]
+    config: Option<PathBuf>,
+}
+
+#[expect(clippy::too_many_lines)]
 fn main() -> Result<()> {
     pretty_env_logger::try_init()?;
     let args = Args::parse();
     match args.command {
         Command::Run(run) => {
-            let mut config: Config = serde_yaml::from_reader(std::fs::File::open(&run.file)?)?;
-            config._folder = run.file.parent().map(ToOwned::to_owned);
+            let mut config = Config::load(&run.file)?;
             let mut fd = FlightData::from_gatelogue()?;
             fd.preprocess(&mut config)?;
+            validate::require_valid(&mut config, &fd)?;
             let old_plan = if let Some(old) = &run.old {
                 Some(update::load_from_out(old.to_owned())?)
             } else {
                 None
             };
-            let mut result = run::run(&mut config, &fd, old_plan.as_ref())?;
+            let mut result = run::run(&mut config, &fd, old_plan.as_ref(), run.seed)?;
             if run.stats {
-                eprintln!("\n{}", stats::get_stats(&result, &mut config)?);
+                let stats = PlanStats::new(&result, &mut config, &fd)?;
+                eprintln!("\n{}", stats.format(run.stats_format)?);
             }
             if let Some(old) = &run.old {
                 result = update::update(old.to_owned(), result, &config)?;
@@ -87,6 +275,9 @@ fn main() -> Result<()> {
                 .map(|f| f.to_string())
                 .collect::<Vec<_>>()
                 .join("\n");
+            if let Some(dir) = config.history_dir() {
+                history::save(&dir, &fd, Some(&res))?;
+            }
             if run.replace {
                 if let Some(old) = &run.old {
                     std::fs::write(old, res)?;
@@ -98,16 +289,218 @@ fn main() -> Result<()> {
                 println!("{res}");
             }
         }
+        Command::Validate(validate) => {
+            let mut config = Config::load(&validate.file)?;
+            let fd = if validate.offline {
+                None
+            } else {
+                Some(FlightData::from_gatelogue()?)
+            };
+            group::resolve(&mut config, fd.as_ref())?;
+            let problems = validate::validate(&mut config, fd.as_ref())?;
+            for problem in &problems {
+                println!("{problem}");
+            }
+            let errors = problems
+                .iter()
+                .filter(|p| matches!(p, validate::Problem::Error(_)))
+                .count();
+            if errors > 0 {
+                return Err(anyhow!(
+                    "Found {errors} error(s) and {} warning(s)",
+                    problems.len() - errors
+                ));
+            }
+            println!("Found no errors and {} warning(s)", problems.len());
+        }
+        Command::Analyze(analyze) => {
+            let mut config = Config::load(&analyze.file)?;
+            let mut fd = FlightData::from_gatelogue()?;
+            fd.preprocess(&mut config)?;
+            validate::require_valid(&mut config, &fd)?;
+            let plan = if let Some(plan) = analyze.plan {
+                update::load_from_out(plan)?
+            } else {
+                run::run(&mut config, &fd, None, None)?
+            };
+            print!(
+                "{}",
+                analyze::analyze(&mut config, &fd, &plan, analyze.top)?
+            );
+        }
+        Command::WhatIf(what_if) => {
+            let mut config = Config::load(&what_if.file)?;
+            let mut fd = FlightData::from_gatelogue()?;
+            fd.preprocess(&mut config)?;
+            validate::require_valid(&mut config, &fd)?;
+            print!(
+                "{}",
+                what_if::what_if(&config, &fd, &what_if.candidates, what_if.seed)?
+            );
+        }
+        Command::Impact(impact) => {
+            let mut config = Config::load(&impact.file)?;
+            let mut fd = FlightData::from_gatelogue()?;
+            fd.preprocess(&mut config)?;
+            validate::require_valid(&mut config, &fd)?;
+            let plan = update::load_from_out(impact.out_file)?;
+            print!("{}", impact::impact(&mut config, &fd, &plan)?);
+        }
+        Command::History(h) => {
+            let mut config = Config::load(&h.file)?;
+            let dir = config.history_dir().ok_or_else(|| {
+                anyhow!(
+                    "Set `history_dir` in {} to keep snapshots",
+                    h.file.display()
+                )
+            })?;
+            if h.snapshot {
+                let mut fd = FlightData::from_gatelogue()?;
+                fd.preprocess(&mut config)?;
+                validate::require_valid(&mut config, &fd)?;
+                history::save(&dir, &fd, None)?;
+            }
+            print!("{}", history::trends(&history::load(&dir)?));
+        }
+        Command::Route(r) => {
+            let mut config = Config::load(&r.file)?;
+            // Gatelogue is only needed to plan or for other airlines' flights
+            let (plan, fd) = match r.plan {
+                Some(plan) if !r.competitors => (update::load_from_out(plan)?, None),
+                plan => {
+                    let mut fd = FlightData::from_gatelogue()?;
+                    fd.preprocess(&mut config)?;
+                    validate::require_valid(&mut config, &fd)?;
+                    let plan = match plan {
+                        Some(plan) => update::load_from_out(plan)?,
+                        None => run::run(&mut config, &fd, None, None)?,
+                    };
+                    (plan, Some(fd))
+                }
+            };
+            let itineraries = route::find(
+                &plan,
+                fd.as_ref().filter(|_| r.competitors),
+                &r.from,
+                &r.to,
+                r.max_transfers,
+                r.limit,
+            )?;
+            if itineraries.is_empty() {
+                println!(
+                    "No itineraries from {} to {} with at most {} transfer(s)",
+                    r.from, r.to, r.max_transfers
+                );
+            }
+            for (i, itinerary) in itineraries.iter().enumerate() {
+                print!("{}. {itinerary}", i + 1);
+            }
+        }
+        Command::Export(e) => {
+            let mut config = Config::load(&e.file)?;
+            // Gatelogue is only needed to plan or for the coordinates of the airports
+            let (plan, fd) = match e.plan {
+                Some(plan) if !matches!(e.format, ExportFormat::GeoJson) => {
+                    (update::load_from_out(plan)?, None)
+                }
+                plan => {
+                    let mut fd = FlightData::from_gatelogue()?;
+                    fd.preprocess(&mut config)?;
+                    validate::require_valid(&mut config, &fd)?;
+                    let plan = match plan {
+                        Some(plan) => update::load_from_out(plan)?,
+                        None => run::run(&mut config, &fd, None, None)?,
+                    };
+                    (plan, Some(fd))
+                }
+            };
+            let exported = export::export(&plan, fd.as_ref(), e.format)?;
+            if let Some(output) = &e.output {
+                std::fs::write(output, exported)?;
+                println!("Written {}", output.display());
+            } else {
+                print!("{exported}");
+            }
+        }
         Command::GetConfig => {
             println!("{}", include_str!("../data/default_config.yml"));
         }
+        Command::Init(init) => {
+            if init.output.exists() && !init.force {
+                return Err(anyhow!(
+                    "{} already exists (use --force to overwrite it)",
+                    init.output.display()
+                ));
+            }
+            let mut config = init::init(
+                &init.output,
+                InitOptions {
+                    airline_name: init.airline_name,
+                    gate_file: init.gate_file,
+                    hubs: init.hubs,
+                    hub_threshold: init.hub_threshold,
+                    interactive: !init.non_interactive,
+                },
+            )?;
+            for problem in validate::validate(&mut config, None)? {
+                println!("{problem}");
+            }
+            std::fs::write(
+                &init.output,
+                format!(
+                    "# Generated by `flight-network-planner init`\n\
+                    # Run `flight-network-planner get-config` for a description of every setting\n{}",
+                    serde_yaml::to_string(&config)?
+                ),
+            )?;
+            println!("Written {}", init.output.display());
+        }
+        Command::MigrateConfig(migrate_config) => {
+            let migrated = migrate::migrate(&migrate_config.file)?;
+            if migrated.changes.is_empty() {
+                println!("{} is already up to date", migrate_config.file.display());
+                return Ok(());
+            }
+            println!("Changes to {}:", migrate_config.file.display());
+            for change in &migrated.changes {
+                println!("  - {change}");
+            }
+            if migrate_config.dry_run {
+                return Ok(());
+            }
+            let output = if let Some(output) = migrate_config.output {
+                output
+            } else {
+                let mut backup = migrate_config.file.clone().into_os_string();
+                backup.push(".bak");
+                std::fs::copy(&migrate_config.file, &backup)?;
+                println!("Original kept as {}", PathBuf::from(backup).display());
+                migrate_config.file
+            };
+            std::fs::write(&output, migrated.source)?;
+            println!("Written {}", output.display());
+        }
+        Command::Schema(schema) => {
+            let sizes = if let Some(file) = &schema.config {
+                Config::load(file)?
+                    .sizes
+                    .into_iter()
+                    .map(|s| s.name)
+                    .collect()
+            } else {
+                vec![]
+            };
+            println!("{}", schema::schema(&sizes)?);
+        }
         Command::GateKeys(gate_keys) => {
             let flights = update::load_from_out(gate_keys.out_file)?;
-            let mut map: HashMap<_, Vec<_>> = HashMap::new();
-            for flight in flights {
-                map.entry(flight.airport1)
-                    .or_default()
-                    .push((flight.airport2, flight.number));
+            let mut map: BTreeMap<_, Vec<_>> = BTreeMap::new();
+            for flight in &flights {
+                for (from, to) in flight.legs() {
+                    map.entry(from.to_owned())
+                        .or_default()
+                        .push((to.to_owned(), flight.number));
+                }
             }
             let res = map
                 .iter()
@@ -117,6 +510,7 @@ fn main() -> Result<()> {
                         ka,
                         kg,
                         vs.iter()
+                            .sorted()
                             .map(|((va, vg), num)| format!("{num} {va} {vg}"))
                             .join(", ")
                     )
@@ -125,6 +519,15 @@ fn main() -> Result<()> {
                 .join("\n");
             println!("{res}");
         }
+        Command::Wiki(w) => {
+            let templates = if let Some(config) = &w.config {
+                Config::load(config)?.wiki
+            } else {
+                WikiConfig::default()
+            };
+            let flights = update::load_from_out(w.out_file)?;
+            print!("{}", wiki::wiki(&flights, &templates));
+        }
         Command::Completion { shell } => {
             shell.generate(&mut Args::command(), &mut std::io::stdout());
         }
@@ -1,15 +1,27 @@
 use smol_str::SmolStr;
 
 pub mod config;
+pub mod config_error;
+pub mod config_merge;
 pub mod flight;
 pub mod flight_data;
 pub mod flight_type;
 pub mod flight_utils;
 pub mod fng;
 pub mod gate;
+pub mod gate_file;
+pub mod group;
+pub mod limits;
+pub mod rule;
+pub mod size;
+pub mod tag_rule;
+pub mod tier;
 
 pub type AirlineName = SmolStr;
 pub type AirportCode = SmolStr;
 pub type GateCode = SmolStr;
+pub type GroupName = SmolStr;
 pub type FlightNumber = u16;
 pub type Size = SmolStr;
+pub type Tag = SmolStr;
+pub type TierName = SmolStr;
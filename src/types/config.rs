@@ -1,47 +1,293 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Result};
-use counter::Counter;
 use itertools::Itertools;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::types::{
+    config_error::ConfigError,
+    config_merge,
+    flight_type::FlightType,
     gate::{Gate, PartialGate},
-    AirlineName, AirportCode, FlightNumber, GateCode,
+    gate_file,
+    group::Groups,
+    limits::{Limit, LimitOverrides},
+    rule::{self, Route, Rule, Verdict},
+    size::SizeConfig,
+    tag_rule::TagRule,
+    tier::{Tier, TierPair},
+    AirlineName, AirportCode, FlightNumber, GateCode, GroupName, Size, TierName,
 };
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// The current version of the config format
+pub const CONFIG_VERSION: u8 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+#[schemars(transform = config_merge::add_to_schema)]
 pub struct Config {
+    /// The version of the config format, which `migrate-config` upgrades older files from
+    /// (default: 1, the version before this setting was added)
+    #[serde(rename = "config_version", default = "Config::first_version")]
+    pub version: u8,
+    /// The name of the airline
     pub airline_name: AirlineName,
+    /// The airlines to be ignored in the flight data (default: the value in `airline_name`)
     ignored_airlines: Vec<AirlineName>,
+    /// Named groups of airports, which lists of airports can refer to as `$<name>`.
+    /// Groups can contain other groups, and `$world:old`, `$world:new` and `$town:<name>`
+    /// are built from Gatelogue
+    pub groups: BTreeMap<GroupName, Vec<AirportCode>>,
+    /// A list of hubs for your airline
+    /// (default: all airports with at least the number of gates in `hub_threshold`)
     hubs: Vec<AirportCode>,
+    /// The minimum number of gates for an airport to be considered a hub if `hubs` is empty
     hub_threshold: usize,
+    /// The range of flight numbers for hub-to-hub flights
     pub range_h2h: Vec<(FlightNumber, FlightNumber)>,
+    /// The range of flight numbers for non-hub-to-non-hub flights
     pub range_n2n: Vec<(FlightNumber, FlightNumber)>,
-    pub range_h2n: HashMap<AirportCode, Vec<(FlightNumber, FlightNumber)>>,
+    /// The range of flight numbers for hub-to-non-hub flights, for each hub
+    pub range_h2n: BTreeMap<AirportCode, Vec<(FlightNumber, FlightNumber)>>,
+    /// Whether flights use the same number for both directions
     pub both_dir_same_num: bool,
+    /// The path of the file, relative to the folder of the config, of all gates at each airport.
+    /// CSV and TSV files have a header row naming the `airport`, `code` and `size` columns,
+    /// and optionally `disabled`, `tags`, `max_dests`, `min_dests` and `note`.
+    /// YAML and JSON files are laid out like `gates`.
+    /// Other files have one `<airport> <gate_no> <size>` row for each gate
     pub gate_file: Option<PathBuf>,
-    pub gates: HashMap<AirportCode, Vec<PartialGate>>,
+    /// The gates at each airport, used if `gate_file` is not given
+    pub gates: BTreeMap<AirportCode, Vec<PartialGate>>,
+    /// The hard maximum number of warps in a plane for hub airports
     pub hard_max_hub: u8,
+    /// The hard maximum number of warps in a plane for non-hub airports
     pub hard_max_nonhub: u8,
+    /// The maximum number of hub-to-hub warps in a plane at a hub
     pub max_h2h: u8,
+    /// The maximum number of hub-to-nonhub warps in a plane at a hub
     pub max_h2n_hub: u8,
+    /// The maximum number of hub-to-nonhub warps in a plane at a nonhub
     pub max_h2n_nonhub: u8,
+    /// The maximum number of nonhub-to-nonhub warps in a plane at a nonhub
     pub max_n2n: u8,
+    /// Sets of airports that cannot be flown between one another
     pub restricted_between: Vec<Vec<AirportCode>>,
-    pub restricted_to: HashMap<AirportCode, Vec<AirportCode>>,
+    /// The destinations that cannot be flown to from each airport
+    pub restricted_to: BTreeMap<AirportCode, Vec<AirportCode>>,
+    /// Airports where duplicate flights are strictly not allowed
     pub no_dupes: Vec<AirportCode>,
+    /// Sets of airports that are preferred to be flown between one another
     pub preferred_between: Vec<Vec<AirportCode>>,
-    pub preferred_to: HashMap<AirportCode, Vec<AirportCode>>,
-    pub gate_allowed_dests: HashMap<AirportCode, HashMap<GateCode, Vec<AirportCode>>>,
-    pub gate_denied_dests: HashMap<AirportCode, HashMap<GateCode, Vec<AirportCode>>>,
-    pub max_dests_per_gate: HashMap<AirportCode, u8>,
+    /// The destinations that are preferred to be flown to from each airport
+    pub preferred_to: BTreeMap<AirportCode, Vec<AirportCode>>,
+    /// The only destinations that can be flown to from each gate of each airport
+    pub gate_allowed_dests: BTreeMap<AirportCode, BTreeMap<GateCode, Vec<AirportCode>>>,
+    /// The destinations that cannot be flown to from each gate of each airport
+    pub gate_denied_dests: BTreeMap<AirportCode, BTreeMap<GateCode, Vec<AirportCode>>>,
+    /// The maximum number of destinations of each gate of each airport
+    pub max_dests_per_gate: BTreeMap<AirportCode, u8>,
+    /// The minimum number of destinations of each airport, which the planner satisfies
+    /// before planning other flights
+    pub min_dests: BTreeMap<AirportCode, u8>,
+    /// The minimum number of destinations of each gate of each airport,
+    /// which the planner satisfies before planning other flights
+    pub min_dests_per_gate: BTreeMap<AirportCode, u8>,
+    /// Restrictions and preferences for the flights from gates with a tag
+    pub tag_rules: Vec<TagRule>,
+    /// Rules that forbid routes, require them to go somewhere or change their score,
+    /// matching them by airport, group, gate, tag, size, tier and flight type
+    pub rules: Vec<Rule>,
+    /// The tiers of airports, in order of decreasing importance, used instead of hubs and non-hubs
+    tiers: Vec<Tier>,
+    /// The settings for flights between each pair of tiers
+    tier_pairs: Vec<TierPair>,
+    /// Overrides of the warp limits, `hub_threshold` and `max_dests_per_gate`
+    pub limit_overrides: LimitOverrides,
+    /// The known gate sizes
+    pub sizes: Vec<SizeConfig>,
+    /// Flights that stop at intermediate airports under one flight number
+    pub multi_stop: MultiStopConfig,
+    /// Requirements on how well the planned flights connect the airports
+    pub connectivity: ConnectivityConfig,
+    /// The folder, relative to the folder of the config, that `run` saves snapshots of the
+    /// flight data and the plan to, for `history` (default: no snapshots are saved)
+    pub history_dir: Option<PathBuf>,
+    /// The templates of the tables that `wiki` makes
+    pub wiki: WikiConfig,
     #[serde(skip)]
     _gates: Vec<Gate>,
     #[serde(skip)]
     pub _folder: Option<PathBuf>,
+    /// The airports in each group, once the groups are resolved
+    #[serde(skip)]
+    pub _groups: BTreeMap<GroupName, Vec<AirportCode>>,
+    /// `rules` with the rules made from the other settings, once they are first used
+    #[serde(skip)]
+    _rules: Option<Vec<Rule>>,
+    /// The tier of each airport and gate size, once it is first looked up
+    #[serde(skip)]
+    _tier_of: BTreeMap<(AirportCode, Size), usize>,
+}
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MultiStopConfig {
+    /// Whether to plan multi-stop flights
+    pub enabled: bool,
+    /// The maximum number of legs in a multi-stop flight
+    pub max_legs: u8,
+    /// Multi-stop flights must start or end at an airport with at most this many gates
+    pub small_airport_max_gates: usize,
+    /// The maximum number of multi-stop flights that start or end at a gate
+    pub max_per_gate: u8,
+    /// The range of flight numbers for multi-stop flights
+    pub range: Vec<(FlightNumber, FlightNumber)>,
+}
+/// Requirements on how well the planned flights connect the airports. Flights are added to the plan
+/// to meet them if they are not met, and the plan is rejected if they still cannot be met
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectivityConfig {
+    /// Whether every airport outside the first tier (the non-hubs, by default)
+    /// must have a flight to an airport in the first tier
+    pub every_nonhub_connects_to_hub: bool,
+    /// The most transfers that travelling between any two airports may need (default: no limit)
+    pub max_transfers_between_any_two_airports: Option<u8>,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            airline_name: AirlineName::default(),
+            ignored_airlines: vec![],
+            groups: BTreeMap::new(),
+            hubs: vec![],
+            hub_threshold: 5,
+            range_h2h: vec![(1, 100)],
+            range_n2n: vec![(901, 1000)],
+            range_h2n: BTreeMap::new(),
+            both_dir_same_num: true,
+            gate_file: None,
+            gates: BTreeMap::new(),
+            hard_max_hub: 6,
+            hard_max_nonhub: 6,
+            max_h2h: 6,
+            max_h2n_hub: 6,
+            max_h2n_nonhub: 6,
+            max_n2n: 6,
+            restricted_between: vec![],
+            restricted_to: BTreeMap::new(),
+            no_dupes: vec![],
+            preferred_between: vec![],
+            preferred_to: BTreeMap::new(),
+            gate_allowed_dests: BTreeMap::new(),
+            gate_denied_dests: BTreeMap::new(),
+            max_dests_per_gate: BTreeMap::new(),
+            min_dests: BTreeMap::new(),
+            min_dests_per_gate: BTreeMap::new(),
+            tag_rules: vec![],
+            rules: vec![],
+            tiers: vec![],
+            tier_pairs: vec![],
+            limit_overrides: LimitOverrides::default(),
+            sizes: vec![],
+            multi_stop: MultiStopConfig::default(),
+            connectivity: ConnectivityConfig::default(),
+            history_dir: None,
+            wiki: WikiConfig::default(),
+            _gates: vec![],
+            _folder: None,
+            _groups: BTreeMap::new(),
+            _rules: None,
+            _tier_of: BTreeMap::new(),
+        }
+    }
+}
+/// The templates of the tables that `wiki` makes. `{name}` in a template is replaced by the value
+/// of `name`, as listed for each template
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct WikiConfig {
+    /// The header row of the table of flights
+    pub flight_header: String,
+    /// A row of the table of flights, with `{number}`, `{from}`, `{from_gate}`, `{to}`, `{to_gate}`,
+    /// `{via}`, `{size}` and `{type}`
+    pub flight_row: String,
+    /// The heading above the departure board of an airport, with `{airport}`
+    pub board_heading: String,
+    /// The header row of a departure board
+    pub board_header: String,
+    /// The row before the departures from a gate, with `{airport}` and `{gate}`
+    pub board_gate: String,
+    /// A departure on a departure board, with `{number}`, `{gate}`, `{to}`, `{to_gate}`, `{size}` and `{type}`
+    pub board_row: String,
+}
+impl Default for WikiConfig {
+    fn default() -> Self {
+        Self {
+            flight_header: "! Flight !! From !! Gate !! To !! Gate !! Size".into(),
+            flight_row: "| {number} || {from} || {from_gate} || {to} || {to_gate} || {size}".into(),
+            board_heading: "=== {airport} ===".into(),
+            board_header: "! Flight !! To !! Gate !! Size".into(),
+            board_gate: "! colspan=\"4\" | Gate {gate}".into(),
+            board_row: "| {number} || {to} || {to_gate} || {size}".into(),
+        }
+    }
+}
+impl Default for MultiStopConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_legs: 2,
+            small_airport_max_gates: 1,
+            max_per_gate: 1,
+            range: vec![],
+        }
+    }
 }
+
 impl Config {
+    /// Reads the config from a YAML file, with the files it extends merged in
+    pub fn load(file: &Path) -> Result<Self> {
+        let source = std::fs::read_to_string(file)?;
+        let value: serde_yaml::Value =
+            serde_yaml::from_str(&source).map_err(|e| ConfigError::new(file, &source, &e))?;
+        let mut config: Self = if config_merge::extends(&value)?.is_empty() {
+            config_merge::reject_appends(file, &source, &value)?;
+            config_merge::require_airline_name(file, &value)?;
+            serde_yaml::from_str(&source).map_err(|e| ConfigError::new(file, &source, &e))?
+        } else {
+            // each file is checked on its own as it is merged in, where its errors can be located
+            let merged = config_merge::load(file, &mut vec![])?;
+            config_merge::require_airline_name(file, &merged)?;
+            serde_yaml::from_value(merged).map_err(|e| ConfigError::new(file, "", &e))?
+        };
+        if config.version > CONFIG_VERSION {
+            return Err(anyhow!(
+                "{} is in version {} of the config format, but only versions up to {CONFIG_VERSION} are supported (upgrade flight-network-planner to read it)",
+                file.display(),
+                config.version
+            ));
+        }
+        config._folder = file.parent().map(ToOwned::to_owned);
+        Ok(config)
+    }
+    const fn first_version() -> u8 {
+        1
+    }
+    /// Replaces the groups in `hubs` and in the airports of `tiers` with their airports
+    pub fn expand_groups(&mut self, groups: &mut Groups) -> Result<()> {
+        self.hubs = groups.expand(&self.hubs, "hubs")?;
+        for tier in &mut self.tiers {
+            tier.airports = groups.expand(&tier.airports, "tiers")?;
+        }
+        self._tier_of.clear();
+        Ok(())
+    }
     pub fn airports(&mut self) -> Result<Vec<AirportCode>> {
         Ok(self
             .gates()?
@@ -51,55 +297,304 @@ impl Config {
             .dedup()
             .collect())
     }
+    /// Every airport that is a hub (in the first tier) for at least one of its gate sizes
     pub fn hubs(&mut self) -> Result<Vec<AirportCode>> {
         Ok(if self.hubs.is_empty() {
-            self.gates()?
-                .into_iter()
-                .map(|g| g.airport)
-                .collect::<Counter<_>>()
+            let mut hubs = vec![];
+            for (airport, size) in self
+                .gates()?
                 .into_iter()
-                .filter(|(_, c)| *c >= self.hub_threshold)
-                .map(|(a, _)| a)
-                .collect::<Vec<_>>()
+                .map(|g| (g.airport, g.size))
+                .sorted()
+                .dedup()
+            {
+                if !hubs.contains(&airport) && self.tier(&airport, &size)? == 0 {
+                    hubs.push(airport);
+                }
+            }
+            hubs
         } else {
             self.hubs.clone()
         })
     }
+    /// Sets the hubs, or clears them so that they are found with `hub_threshold`
+    pub fn set_hubs(&mut self, hubs: Vec<AirportCode>, hub_threshold: usize) {
+        self.hubs = hubs;
+        self.hub_threshold = hub_threshold;
+        self._tier_of.clear();
+    }
+    /// The hub tiers, in order of decreasing importance.
+    /// If none are configured, these are the hub (`H`) and non-hub (`N`) tiers
+    pub fn tiers(&self) -> Vec<Tier> {
+        if !self.tiers.is_empty() {
+            return self.tiers.clone();
+        }
+        vec![
+            Tier {
+                name: "H".into(),
+                airports: self.hubs.clone(),
+                min_gates: Some(self.hub_threshold),
+                hard_max: self.hard_max_hub,
+            },
+            Tier {
+                name: "N".into(),
+                airports: vec![],
+                min_gates: None,
+                hard_max: self.hard_max_nonhub,
+            },
+        ]
+    }
+    /// The name of the tier with index `i` in [`Self::tiers`], without making the default tiers
+    pub fn tier_name(&self, i: usize) -> TierName {
+        if self.tiers.is_empty() {
+            return if i == 0 { "H" } else { "N" }.into();
+        }
+        self.tiers[i].name.clone()
+    }
+    /// The settings for flights between each pair of tiers.
+    /// If no tiers are configured, these are made from `range_XXX` and `max_XXX`
+    pub fn tier_pairs(&self) -> Vec<TierPair> {
+        if !self.tiers.is_empty() {
+            return self.tier_pairs.clone();
+        }
+        vec![
+            TierPair {
+                tiers: ("H".into(), "H".into()),
+                range: self.range_h2h.clone(),
+                range_per_airport: BTreeMap::new(),
+                score: 6,
+                existing_score: 5,
+                max: (self.max_h2h, self.max_h2h),
+            },
+            TierPair {
+                tiers: ("H".into(), "N".into()),
+                range: vec![],
+                range_per_airport: self.range_h2n.clone(),
+                score: 3,
+                existing_score: 1,
+                max: (self.max_h2n_hub, self.max_h2n_nonhub),
+            },
+            TierPair {
+                tiers: ("N".into(), "N".into()),
+                range: self.range_n2n.clone(),
+                range_per_airport: BTreeMap::new(),
+                score: 2,
+                existing_score: -1,
+                max: (self.max_n2n, self.max_n2n),
+            },
+        ]
+    }
+    /// The settings for flights of type `ty`
+    pub fn tier_pair(&self, ty: &FlightType) -> Option<TierPair> {
+        self.tier_pairs()
+            .into_iter()
+            .find(|p| p.matches(&ty.tier1, &ty.tier2))
+    }
+    /// The index in [`Self::tiers`] of the tier of gates of size `size` at `airport`.
+    /// Airports listed in a tier's `airports` are in that tier; otherwise an airport is in the first
+    /// tier without a list whose `min_gates` it meets. If `hub_threshold` is overridden for the
+    /// size, it replaces the first tier's `min_gates` and only gates of that size are counted
+    pub fn tier(&mut self, airport: &AirportCode, size: &Size) -> Result<usize> {
+        let key = (airport.clone(), size.clone());
+        if let Some(tier) = self._tier_of.get(&key) {
+            return Ok(*tier);
+        }
+        self.load_gates()?;
+        let tier = self.find_tier(airport, size);
+        self._tier_of.insert(key, tier);
+        Ok(tier)
+    }
+    /// [`Self::tier`], worked out from the gates, which must be loaded
+    fn find_tier(&self, airport: &AirportCode, size: &Size) -> usize {
+        let tiers = self.tiers();
+        let gates = &self._gates;
+        for (i, tier) in tiers.iter().enumerate() {
+            if !tier.airports.is_empty() {
+                if tier.airports.contains(airport) {
+                    return i;
+                }
+                continue;
+            }
+            let overrides = &self.limit_overrides;
+            let (threshold, size_specific) = if i > 0 {
+                (tier.min_gates, false)
+            } else if let Some(t) = overrides.get_for_size(Limit::HubThreshold, airport, size) {
+                (Some(t), true)
+            } else if let Some(t) = overrides
+                .airports
+                .get(airport)
+                .and_then(|l| l.get(Limit::HubThreshold))
+            {
+                (Some(t), false)
+            } else if let Some(t) = overrides
+                .sizes
+                .get(size)
+                .and_then(|l| l.get(Limit::HubThreshold))
+            {
+                (Some(t), true)
+            } else {
+                (tier.min_gates, false)
+            };
+            if threshold.is_none_or(|threshold| {
+                gates
+                    .iter()
+                    .filter(|g| g.airport == *airport && (!size_specific || g.size == *size))
+                    .count()
+                    >= threshold
+            }) {
+                return i;
+            }
+        }
+        tiers.len().saturating_sub(1)
+    }
+    /// The index of the most important tier `airport` is in, over all of its gate sizes
+    pub fn airport_tier(&mut self, airport: &AirportCode) -> Result<usize> {
+        let sizes = self
+            .load_gates()?
+            .iter()
+            .filter(|g| g.airport == *airport)
+            .map(|g| g.size.clone())
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>();
+        if sizes.is_empty() {
+            return self.tier(airport, &Size::default());
+        }
+        let mut tier = usize::MAX;
+        for size in sizes {
+            tier = tier.min(self.tier(airport, &size)?);
+        }
+        Ok(tier)
+    }
+    /// The type of a flight between airports in the tiers with indices `tier1` and `tier2`
+    pub fn flight_type(&self, tier1: usize, tier2: usize, existing: bool) -> FlightType {
+        FlightType {
+            tier1: self.tier_name(tier1.min(tier2)),
+            tier2: self.tier_name(tier1.max(tier2)),
+            existing,
+        }
+    }
+    /// The score of a flight of type `ty`
+    pub fn type_score(&self, ty: &FlightType) -> i8 {
+        self.tier_pair(ty).map_or(0, |p| {
+            if ty.existing {
+                p.existing_score
+            } else {
+                p.score
+            }
+        })
+    }
+    /// The value of `limit` for gates of size `size` at `airport`,
+    /// with the most specific override winning over the global value
+    pub fn limit(&self, limit: Limit, airport: &AirportCode, size: &Size) -> Option<usize> {
+        self.limit_overrides
+            .get(limit, airport, size)
+            .or_else(|| match limit {
+                Limit::MaxDestsPerGate => self
+                    .max_dests_per_gate
+                    .get(airport)
+                    .map(|n| usize::from(*n)),
+                _ => None,
+            })
+            .or_else(|| match limit {
+                Limit::HardMax { tier } => self
+                    .tiers()
+                    .into_iter()
+                    .find(|t| t.name == *tier)
+                    .map(|t| t.hard_max.into()),
+                Limit::TypeMax { pair, tier } => self
+                    .tier_pairs()
+                    .into_iter()
+                    .find(|p| p.name() == pair)
+                    .map(|p| p.max_at(tier).into()),
+                Limit::HubThreshold => self.tiers().first()?.min_gates,
+                Limit::MaxDestsPerGate => None,
+            })
+    }
+    /// The maximum number of destinations of the gate set by its `max_dests` or by `max_dests_per_gate`
+    pub fn max_dests(&self, g: &Gate) -> Option<usize> {
+        g.max_dests
+            .map(usize::from)
+            .or_else(|| self.limit(Limit::MaxDestsPerGate, &g.airport, &g.size))
+    }
+    /// The minimum number of destinations of the gate set by its `min_dests` or by `min_dests_per_gate`
+    pub fn min_dests(&self, g: &Gate) -> usize {
+        g.min_dests
+            .or_else(|| self.min_dests_per_gate.get(&g.airport).copied())
+            .map_or(0, usize::from)
+    }
+    /// The maximum number of destinations the gate can have
+    pub fn hard_max(&mut self, g: &Gate) -> Result<usize> {
+        if let Some(n) = self.max_dests(g) {
+            return Ok(n);
+        }
+        let tier = self.tier(&g.airport, &g.size)?;
+        let tier = self.tier_name(tier);
+        Ok(self
+            .limit(Limit::HardMax { tier: &tier }, &g.airport, &g.size)
+            .unwrap_or(usize::MAX))
+    }
+    /// The maximum number of destinations of flight type `ty` the gate can have
+    pub fn type_max(&mut self, g: &Gate, ty: &FlightType) -> Result<usize> {
+        let tier = self.tier(&g.airport, &g.size)?;
+        let tier = self.tier_name(tier);
+        let pair = self
+            .tier_pair(ty)
+            .map_or_else(|| ty.pair_name(), |p| p.name());
+        Ok(self
+            .limit(
+                Limit::TypeMax {
+                    pair: &pair,
+                    tier: &tier,
+                },
+                &g.airport,
+                &g.size,
+            )
+            .unwrap_or(usize::MAX))
+    }
+    /// What the rules decide about `route`. The rules are made from the other settings
+    /// the first time this is called, so groups must be resolved before then
+    pub fn judge(&mut self, route: &Route) -> Verdict {
+        if self._rules.is_none() {
+            self._rules = Some(rule::all_rules(self));
+        }
+        rule::judge(self._rules.as_deref().unwrap_or_default(), route)
+    }
     pub fn gates(&mut self) -> Result<Vec<Gate>> {
+        Ok(self.load_gates()?.to_vec())
+    }
+    /// The gates, read from `gate_file` or `gates` the first time
+    fn load_gates(&mut self) -> Result<&[Gate]> {
         if self._gates.is_empty() {
             let gates = if let Some(gate_file) = &self.gate_file {
-                let gate_file = self._folder.as_ref().map_or_else(|| gate_file.to_owned(), |folder| folder.join(gate_file));
-                std::fs::read_to_string(gate_file)?
-                    .split('\n')
-                    .filter(|l| !l.trim().is_empty())
-                    .map(|l| {
-                        Some({
-                            let params = l.split(' ').collect::<Vec<_>>();
-                            Gate {
-                                airport: params.first()?.trim().into(),
-                                code: params.get(1)?.trim().into(),
-                                size: params.get(2)?.trim().into(),
-                            }
-                        })
-                    })
-                    .collect::<Option<Vec<_>>>()
-                    .ok_or_else(|| anyhow!("Invalid gate file"))?
+                let gate_file = self
+                    ._folder
+                    .as_ref()
+                    .map_or_else(|| gate_file.to_owned(), |folder| folder.join(gate_file));
+                gate_file::read(&gate_file)?
             } else {
-                self.gates
-                    .iter()
-                    .flat_map(|(a, pgs)| {
-                        pgs.iter().map(|pg| Gate {
-                            airport: a.to_owned(),
-                            code: pg.code.clone(),
-                            size: pg.size.clone(),
-                        })
-                    })
-                    .collect()
+                gate_file::from_partial(&self.gates)
             };
 
             self._gates = gates;
         }
-        Ok(self._gates.clone())
+        Ok(&self._gates)
+    }
+    /// Adds `gates` to the gates read from `gate_file` or `gates`, without changing either
+    pub fn add_gates(&mut self, gates: Vec<Gate>) -> Result<()> {
+        self.load_gates()?;
+        self._gates.extend(gates);
+        self._tier_of.clear();
+        Ok(())
+    }
+    /// `history_dir`, relative to the current folder
+    pub fn history_dir(&self) -> Option<PathBuf> {
+        let dir = self.history_dir.as_ref()?;
+        Some(
+            self._folder
+                .as_ref()
+                .map_or_else(|| dir.to_owned(), |folder| folder.join(dir)),
+        )
     }
     pub fn ignored_airlines(&self) -> Vec<AirlineName> {
         if self.ignored_airlines.is_empty() {
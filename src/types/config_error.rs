@@ -0,0 +1,157 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+/// An error in a config file, with where in the file it is and a hint on how to fix it
+#[derive(Debug)]
+pub struct ConfigError {
+    pub file: PathBuf,
+    pub message: String,
+    /// The line and column of the error, both starting from 1
+    pub location: Option<(usize, usize)>,
+    /// The line of the file the error is on
+    pub source_line: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl ConfigError {
+    pub fn new(file: &Path, source: &str, error: &serde_yaml::Error) -> Self {
+        let location = error.location().map(|l| (l.line(), l.column()));
+        let message = error.to_string();
+        let message = location
+            .and_then(|(line, column)| {
+                message
+                    .strip_suffix(&format!(" at line {line} column {column}"))
+                    .map(ToOwned::to_owned)
+            })
+            .unwrap_or(message);
+        let hint = hint(&message);
+        // the known fields are in the hint instead, as the list is long
+        let message = message
+            .split_once(", expected one of")
+            .filter(|(m, _)| m.contains("unknown field"))
+            .map_or_else(|| message.clone(), |(m, _)| m.to_owned());
+        Self {
+            file: file.to_owned(),
+            hint,
+            source_line: location
+                .and_then(|(line, _)| source.lines().nth(line.checked_sub(1)?))
+                .map(ToOwned::to_owned),
+            message,
+            location,
+        }
+    }
+}
+
+impl ConfigError {
+    /// An error on line `line` of `source`, which starts from 1
+    pub fn at_line(
+        file: &Path,
+        source: &str,
+        line: usize,
+        message: String,
+        hint: Option<String>,
+    ) -> Self {
+        Self {
+            file: file.to_owned(),
+            message,
+            location: Some((line, 1)),
+            source_line: source
+                .lines()
+                .nth(line.saturating_sub(1))
+                .map(ToOwned::to_owned),
+            hint,
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        let Some((line, column)) = self.location else {
+            write!(f, " --> {}", self.file.display())?;
+            return self
+                .hint
+                .as_ref()
+                .map_or(Ok(()), |h| write!(f, "\n  = hint: {h}"));
+        };
+        write!(f, " --> {}:{line}:{column}", self.file.display())?;
+        if let Some(source_line) = &self.source_line {
+            let pad = " ".repeat(line.to_string().len());
+            write!(
+                f,
+                "\n{pad} |\n{line} | {source_line}\n{pad} | {}^",
+                " ".repeat(column.saturating_sub(1))
+            )?;
+        }
+        if let Some(hint) = &self.hint {
+            write!(f, "\n  = hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// A hint for fixing the error with the message `message`
+fn hint(message: &str) -> Option<String> {
+    if let Some((_, rest)) = message.split_once("unknown field `") {
+        let (field, expected) = rest.split_once('`')?;
+        let known = expected.split('`').skip(1).step_by(2).collect::<Vec<_>>();
+        let closest = known
+            .iter()
+            .map(|known| (strsim::jaro_winkler(field, known), known))
+            .filter(|(similarity, _)| *similarity > 0.8)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        return Some(closest.map_or_else(
+            || {
+                format!(
+                    "remove it, or use one of the known fields: {}",
+                    known.join(", ")
+                )
+            },
+            |(_, known)| format!("did you mean `{known}`?"),
+        ));
+    }
+    let hints = [
+        (
+            "tuple of size 2",
+            "flight number ranges are written as `[[min1, max1], [min2, max2], ...]`",
+        ),
+        (
+            "expected a sequence",
+            "lists are written as `[a, b, c]`, or with one `- item` per line",
+        ),
+        (
+            "expected a map",
+            "mappings are written as `key: value`, or as `{key: value}` on one line",
+        ),
+        ("expected u8", "this must be a whole number from 0 to 255"),
+        (
+            "expected u16",
+            "this must be a whole number from 0 to 65535",
+        ),
+        (
+            "expected usize",
+            "this must be a whole number of at least 0",
+        ),
+        (
+            "expected i8",
+            "this must be a whole number from -128 to 127",
+        ),
+        ("expected a boolean", "this must be `true` or `false`"),
+        (
+            "did not find expected",
+            "check the indentation of this line and the lines above it",
+        ),
+        (
+            "mapping values are not allowed",
+            "check the indentation of this line, and quote values containing `: `",
+        ),
+    ];
+    hints
+        .iter()
+        .find(|(pattern, _)| message.contains(pattern))
+        .map(|(_, hint)| (*hint).to_owned())
+}
@@ -0,0 +1,346 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+use schemars::Schema;
+use serde_json::json;
+use serde_yaml::{Mapping, Value};
+
+use crate::types::{config::Config, config_error::ConfigError};
+
+/// The keys that list the config files a config is based on
+pub const EXTENDS_KEYS: [&str; 2] = ["extends", "include"];
+
+/// The settings that are paths relative to the folder of the config file they are in
+const PATH_KEYS: [&str; 2] = ["gate_file", "history_dir"];
+
+/// The config files that `value` is based on, relative to its folder
+pub fn extends(value: &Value) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for key in EXTENDS_KEYS {
+        match value.get(key) {
+            None | Some(Value::Null) => {}
+            Some(Value::String(file)) => files.push(file.into()),
+            Some(Value::Sequence(list)) => {
+                for file in list {
+                    files.push(
+                        file.as_str()
+                            .ok_or_else(|| anyhow!("`{key}` must be a file or a list of files"))?
+                            .into(),
+                    );
+                }
+            }
+            Some(_) => return Err(anyhow!("`{key}` must be a file or a list of files")),
+        }
+    }
+    Ok(files)
+}
+
+/// Reads a config file as YAML, with the files it is based on merged in.
+/// `stack` holds the files that are being read, to find files that extend themselves
+pub fn load(file: &Path, stack: &mut Vec<PathBuf>) -> Result<Value> {
+    let canonical = file
+        .canonicalize()
+        .map_err(|e| anyhow!("Could not read {}: {e}", file.display()))?;
+    if stack.contains(&canonical) {
+        return Err(anyhow!("{} extends itself", file.display()));
+    }
+    let source = std::fs::read_to_string(file)?;
+    let value: Value =
+        serde_yaml::from_str(&source).map_err(|e| ConfigError::new(file, &source, &e))?;
+    check(file, &source, &value)?;
+
+    stack.push(canonical.clone());
+    let folder = canonical
+        .parent()
+        .map(ToOwned::to_owned)
+        .unwrap_or_default();
+    let mut merged = Value::Mapping(Mapping::new());
+    for base in extends(&value)? {
+        let base = folder.join(base);
+        let mut base_value = load(&base, stack)?;
+        // the paths in a base are relative to the base, not to the file extending it
+        for key in PATH_KEYS {
+            if let Some(setting) = base_value.get_mut(key) {
+                if let (Some(path), Some(base_folder)) = (setting.as_str(), base.parent()) {
+                    *setting = Value::String(base_folder.join(path).to_string_lossy().into_owned());
+                }
+            }
+        }
+        merge(&mut merged, base_value);
+    }
+    stack.pop();
+
+    merge(&mut merged, value);
+    if let Value::Mapping(m) = &mut merged {
+        for key in EXTENDS_KEYS {
+            m.remove(key);
+        }
+    }
+    Ok(merged)
+}
+
+/// Checks a config file on its own, before it is merged with the files it is based on,
+/// so that errors point at the file and line they are in
+fn check(file: &Path, source: &str, value: &Value) -> Result<()> {
+    reject_appends(file, source, value)?;
+    serde_yaml::from_str::<Config>(&standalone(source))
+        .map_err(|e| ConfigError::new(file, source, &e))?;
+    Ok(())
+}
+
+/// `airline_name` is only required once the files a config is based on are merged in,
+/// as the config's own file or one of its bases may leave it out
+pub fn require_airline_name(file: &Path, value: &Value) -> Result<()> {
+    if value.get("airline_name").is_some() {
+        return Ok(());
+    }
+    Err(ConfigError {
+        file: file.to_owned(),
+        message: "missing field `airline_name`".into(),
+        location: None,
+        source_line: None,
+        hint: Some("set `airline_name` to the name of your airline".into()),
+    }
+    .into())
+}
+
+/// Keys ending in `+` append to the settings of the files a config is based on,
+/// so they are an error in a config that is not based on any
+pub fn reject_appends(file: &Path, source: &str, value: &Value) -> Result<()> {
+    if !extends(value)?.is_empty() {
+        return Ok(());
+    }
+    let Some(path) = append_keys(value).into_iter().next() else {
+        return Ok(());
+    };
+    let key = path.last().map(String::as_str).unwrap_or_default();
+    let message = format!(
+        "`{key}` appends to a setting of the files this config is based on, but it is not based on any"
+    );
+    let hint = Some(format!(
+        "use `{}`, or add `extends`",
+        key.trim_end_matches('+')
+    ));
+    let location = key_location(source, &path);
+    Err(ConfigError {
+        file: file.to_owned(),
+        message,
+        location,
+        source_line: location
+            .and_then(|(line, _)| source.lines().nth(line - 1))
+            .map(ToOwned::to_owned),
+        hint,
+    }
+    .into())
+}
+
+/// The paths of the keys ending in `+` in `value`, at any depth.
+/// The items of lists are not part of the paths
+fn append_keys(value: &Value) -> Vec<Vec<String>> {
+    match value {
+        Value::Mapping(m) => m
+            .iter()
+            .flat_map(|(key, value)| {
+                let key = key.as_str().unwrap_or_default();
+                let nested = append_keys(value).into_iter().map(move |mut path| {
+                    path.insert(0, key.to_owned());
+                    path
+                });
+                key.ends_with('+')
+                    .then(|| vec![key.to_owned()])
+                    .into_iter()
+                    .chain(nested)
+            })
+            .collect(),
+        Value::Sequence(list) => list.iter().flat_map(append_keys).collect(),
+        _ => vec![],
+    }
+}
+
+/// The line and column, both starting from 1, of the key at `path` in `source`. Each key of the path is looked
+/// for at the indentation of the first key in the block of the one before it, skipping comments.
+/// Keys in flow mappings (`{key: value}`) are not found
+fn key_location(source: &str, path: &[String]) -> Option<(usize, usize)> {
+    let mut depth = 0;
+    // the column of the key that the rest of the path is in the block of
+    let mut parent: Option<usize> = None;
+    // the column of the keys in the block, once it is known
+    let mut column = Some(0);
+    for (i, line) in source.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - content.len();
+        // the items of a list can be at the same indentation as the key of the list
+        if parent.is_some_and(|p| indent < p || (indent == p && !content.starts_with('-'))) {
+            return None;
+        }
+        let key = content.trim_start_matches(['-', ' ']);
+        let key_column = line.len() - key.len();
+        if key_column == *column.get_or_insert(key_column)
+            && key
+                .strip_prefix(path[depth].as_str())
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        {
+            depth += 1;
+            if depth == path.len() {
+                return Some((i + 1, key_column + 1));
+            }
+            parent = Some(key_column);
+            column = None;
+        }
+    }
+    None
+}
+
+/// `source` as a config on its own: the keys listing the files it is based on are blanked out,
+/// and the `+` is removed from keys, keeping everything else on the same line and column
+fn standalone(source: &str) -> String {
+    let mut in_extends = false;
+    source
+        .lines()
+        .map(|line| {
+            // a top-level key starts a line, and its value continues on the lines
+            // that are indented or are items of a list
+            if !line.trim().is_empty() && !line.starts_with([' ', '\t', '-', '#']) {
+                in_extends = EXTENDS_KEYS
+                    .iter()
+                    .any(|key| line.starts_with(&format!("{key}:")));
+            }
+            if in_extends {
+                String::new()
+            } else {
+                line.replace("+:", " :")
+            }
+        })
+        .join("\n")
+}
+
+/// Merges `over` into `base`. Mappings are merged key by key, and anything else is replaced.
+/// A key ending in `+` appends its list to the list in `base` instead of replacing it
+pub fn merge(base: &mut Value, over: Value) {
+    match (base, over) {
+        (Value::Mapping(base), Value::Mapping(over)) => {
+            for (key, value) in over {
+                if let Some(name) = key.as_str().and_then(|k| k.strip_suffix('+')) {
+                    let name = Value::from(name);
+                    match (base.get_mut(&name), value) {
+                        (Some(Value::Sequence(list)), Value::Sequence(extra)) => list.extend(extra),
+                        (_, value) => {
+                            base.insert(name, normalised(value));
+                        }
+                    }
+                } else if let Some(existing) = base.get_mut(&key) {
+                    merge(existing, value);
+                } else {
+                    base.insert(key, normalised(value));
+                }
+            }
+        }
+        (base, over) => *base = over,
+    }
+}
+
+/// `value` with the `+` removed from its keys
+fn normalised(value: Value) -> Value {
+    let mut normalised = Value::Mapping(Mapping::new());
+    merge(&mut normalised, value);
+    normalised
+}
+
+/// Adds the keys for extending other config files to the JSON Schema of the config,
+/// as they are merged before the config is read
+pub fn add_to_schema(schema: &mut Schema) {
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        for key in EXTENDS_KEYS {
+            properties.insert(
+                key.into(),
+                json!({
+                    "description": "Config files that this one is based on, relative to its folder. \
+                        This file's settings override theirs, and later files override earlier ones",
+                    "anyOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } }
+                    ]
+                }),
+            );
+        }
+    }
+    // `airline_name` has no default, but a config based on others may get it from them instead
+    if let Some(airline_name) = schema
+        .get_mut("properties")
+        .and_then(|p| p.get_mut("airline_name"))
+        .and_then(|a| a.as_object_mut())
+    {
+        airline_name.remove("default");
+    }
+    let alternatives = std::iter::once("airline_name")
+        .chain(EXTENDS_KEYS)
+        .map(|key| json!({ "required": [key] }))
+        .collect::<Vec<_>>();
+    schema.insert("anyOf".into(), alternatives.into());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(base: &str, over: &str) -> Value {
+        let mut base = serde_yaml::from_str(base).unwrap();
+        merge(&mut base, serde_yaml::from_str(over).unwrap());
+        base
+    }
+
+    fn yaml(source: &str) -> Value {
+        serde_yaml::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn merges_mappings_key_by_key() {
+        assert_eq!(
+            merged("{a: 1, b: {c: 2, d: 3}}", "{b: {d: 4, e: 5}, f: 6}"),
+            yaml("{a: 1, b: {c: 2, d: 4, e: 5}, f: 6}")
+        );
+        assert_eq!(merged("{a: [1, 2]}", "{a: [3]}"), yaml("{a: [3]}"));
+    }
+
+    #[test]
+    fn appends_lists() {
+        assert_eq!(merged("{a: [1, 2]}", "{a+: [3]}"), yaml("{a: [1, 2, 3]}"));
+        assert_eq!(
+            merged("{b: {a: [1]}}", "{b: {a+: [2]}}"),
+            yaml("{b: {a: [1, 2]}}")
+        );
+        // with nothing to append to, the list is set and the `+` is dropped, even in new mappings
+        assert_eq!(merged("{}", "{a+: [1]}"), yaml("{a: [1]}"));
+        assert_eq!(merged("{}", "{b: {a+: [1]}}"), yaml("{b: {a: [1]}}"));
+    }
+
+    #[test]
+    fn null_replaces() {
+        assert_eq!(
+            merged("{a: [1], b: 2}", "{a: null}"),
+            yaml("{a: null, b: 2}")
+        );
+        assert_eq!(merged("{a: [1]}", "{a+: null}"), yaml("{a: null}"));
+        assert_eq!(merged("{a: null}", "{a: {b: 1}}"), yaml("{a: {b: 1}}"));
+    }
+
+    #[test]
+    fn standalone_drops_extends_and_appends() {
+        let source = "extends:\n  - base.yml\n- other.yml\n# comment\nhubs+:\n  - AAA\ninclude: x.yml\nsizes: []";
+        assert_eq!(standalone(source), "\n\n\n\nhubs :\n  - AAA\n\nsizes: []");
+    }
+
+    #[test]
+    fn key_location_skips_comments_and_values() {
+        let source = "a: 'b+: 1'\n# b+: 2\nb:\n  # a+: 3\n  c: 4\n  a+: [1]\n";
+        assert_eq!(
+            key_location(source, &["b".into(), "a+".into()]),
+            Some((6, 3))
+        );
+        assert_eq!(key_location(source, &["a+".into()]), None);
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use itertools::Itertools;
+
 use crate::types::{flight_type::FlightType, AirportCode, FlightNumber, GateCode, Size};
 
 #[derive(Debug, Clone)]
@@ -7,24 +9,47 @@ pub struct Flight {
     pub number: FlightNumber,
     pub airport1: (AirportCode, GateCode),
     pub airport2: (AirportCode, GateCode),
+    /// Intermediate stops between `airport1` and `airport2`, in order of travel
+    pub via: Vec<(AirportCode, GateCode)>,
     pub size: Size,
     pub score: i8,
     pub ty: FlightType,
 }
 
+impl Flight {
+    /// Every stop of the flight in order of travel, including the origin and the destination
+    pub fn stops(&self) -> impl Iterator<Item = &(AirportCode, GateCode)> + Clone {
+        std::iter::once(&self.airport1)
+            .chain(&self.via)
+            .chain(std::iter::once(&self.airport2))
+    }
+    /// Every pair of stops that the flight connects, in order of travel
+    pub fn legs(
+        &self,
+    ) -> impl Iterator<Item = (&(AirportCode, GateCode), &(AirportCode, GateCode))> {
+        self.stops().tuple_windows()
+    }
+}
+
 impl Display for Flight {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{} ({}): {} {} {} {} ({}, {})",
+            "{} ({}): {} {} {} {}",
             self.number,
             self.size,
             self.airport1.0,
             self.airport1.1,
             self.airport2.0,
             self.airport2.1,
-            self.score,
-            self.ty
-        )
+        )?;
+        if !self.via.is_empty() {
+            write!(
+                f,
+                " via {}",
+                self.via.iter().map(|(a, g)| format!("{a} {g}")).join(", ")
+            )?;
+        }
+        write!(f, " ({}, {})", self.score, self.ty)
     }
 }
@@ -1,26 +1,33 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Result};
 use gatelogue_types::{GatelogueData, World};
 use itertools::Itertools;
-use log::{debug, info, warn};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
 
-use crate::types::{config::Config, AirlineName, AirportCode, SmolStr};
+use crate::types::{config::Config, group, AirlineName, AirportCode, SmolStr};
 
-#[expect(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FlightDataFlight {
     pub airline: AirlineName,
     pub flight_number: SmolStr,
     pub airports: Vec<AirportCode>,
 }
 
-#[expect(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct FlightData {
     pub flights: Vec<FlightDataFlight>,
     pub old_world_airports: Vec<AirportCode>,
     pub new_world_airports: Vec<AirportCode>,
+    /// The coordinates of the airports that have them in Gatelogue
+    pub coordinates: BTreeMap<AirportCode, (f64, f64)>,
+    /// The nearest town to each airport that has one in Gatelogue
+    pub towns: BTreeMap<AirportCode, SmolStr>,
+    /// When the data was downloaded, in seconds since the Unix epoch
     pub timestamp: u64,
 }
 impl FlightData {
@@ -53,7 +60,16 @@ impl FlightData {
                     airports: airport_codes,
                 })
             })
-            .collect::<Result<Vec<_>>>()?;
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .sorted_by(|a: &FlightDataFlight, b| {
+                (&a.airline, &a.flight_number, &a.airports).cmp(&(
+                    &b.airline,
+                    &b.flight_number,
+                    &b.airports,
+                ))
+            })
+            .collect();
 
         let old_world_airports = data
             .nodes
@@ -61,6 +77,7 @@ impl FlightData {
             .filter_map(|a| a.as_air_airport())
             .filter(|a| a.common.world.as_ref().is_some_and(|a| **a == World::Old))
             .map(|a| a.code.clone().into())
+            .sorted()
             .collect();
 
         let new_world_airports = data
@@ -69,12 +86,40 @@ impl FlightData {
             .filter_map(|a| a.as_air_airport())
             .filter(|a| a.common.world.as_ref().is_none_or(|a| **a == World::New))
             .map(|a| a.code.clone().into())
+            .sorted()
+            .collect();
+
+        let coordinates = data
+            .nodes
+            .values()
+            .filter_map(|a| a.as_air_airport())
+            .filter_map(|a| Some((a.code.clone().into(), **a.common.coordinates.as_ref()?)))
+            .collect();
+
+        let towns = data
+            .nodes
+            .values()
+            .filter_map(|a| a.as_air_airport())
+            .filter_map(|a| {
+                let (town, _) = a
+                    .common
+                    .proximity
+                    .iter()
+                    .filter_map(|(id, p)| {
+                        let town = data.nodes.get(id)?.as_town()?;
+                        Some((town.name.clone(), p.distance.unwrap_or(f64::MAX)))
+                    })
+                    .min_by(|(a, d1), (b, d2)| d1.total_cmp(d2).then_with(|| a.cmp(b)))?;
+                Some((a.code.clone().into(), town.into()))
+            })
             .collect();
 
         Ok(Self {
             flights,
             old_world_airports,
             new_world_airports,
+            coordinates,
+            towns,
             timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
         })
     }
@@ -84,41 +129,8 @@ impl FlightData {
         self.flights
             .retain(|f| !config.ignored_airlines().contains(&f.airline));
 
-        debug!("Checking airport codes");
-        config
-            .gates()?
-            .iter()
-            .map(|g| g.airport.clone())
-            .sorted()
-            .dedup()
-            .filter(|a| {
-                !self.new_world_airports.contains(a) && !self.old_world_airports.contains(a)
-            })
-            .for_each(|a| {
-                warn!("Airport `{a}` doesn't exist");
-            });
-
-        let airports = config.airports()?;
-        config
-            .hubs()?
-            .into_iter()
-            .filter(|a| !airports.iter().contains(a))
-            .for_each(|a| {
-                warn!("Airport `{a}` has no gates but is stated as a hub");
-            });
-
-        debug!("Ensuring flight number allocations for hubs");
-        let fnr_not_specified = config
-            .hubs()?
-            .into_iter()
-            .filter(|a| !config.range_h2n.keys().contains(a))
-            .collect::<Vec<_>>();
-        if !fnr_not_specified.is_empty() {
-            return Err(anyhow!(
-                "Flight number range not specified for: {}",
-                fnr_not_specified.join(", ")
-            ));
-        }
+        debug!("Resolving airport groups");
+        group::resolve(config, Some(self))?;
         Ok(())
     }
     pub fn num_flights(&self, airport1: &AirportCode, airport2: &AirportCode) -> usize {
@@ -127,4 +139,13 @@ impl FlightData {
             .filter(|f| f.airports.contains(airport1) && f.airports.contains(airport2))
             .count()
     }
+    /// The distance in blocks between two airports, if both have coordinates and are in the same world
+    pub fn distance(&self, airport1: &AirportCode, airport2: &AirportCode) -> Option<f64> {
+        let ((x1, z1), (x2, z2)) = (
+            self.coordinates.get(airport1)?,
+            self.coordinates.get(airport2)?,
+        );
+        (self.old_world_airports.contains(airport1) == self.old_world_airports.contains(airport2))
+            .then(|| (x1 - x2).hypot(z1 - z2))
+    }
 }
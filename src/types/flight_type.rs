@@ -1,37 +1,53 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
-pub enum FlightType {
-    NonExistingH2H,
-    ExistingH2H,
-    NonExistingH2N,
-    NonExistingN2N,
-    ExistingH2N,
-    ExistingN2N,
+use anyhow::{anyhow, Error};
+
+use crate::types::TierName;
+
+/// The type of a flight, given by the tiers of the airports at both ends
+/// (the more important tier first) and whether other airlines already fly the route
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
+pub struct FlightType {
+    pub tier1: TierName,
+    pub tier2: TierName,
+    pub existing: bool,
 }
 
 impl Display for FlightType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::NonExistingH2H => write!(f, "H2Hn"),
-            Self::ExistingH2H => write!(f, "H2He"),
-            Self::NonExistingH2N => write!(f, "H2Nn"),
-            Self::NonExistingN2N => write!(f, "N2Nn"),
-            Self::ExistingH2N => write!(f, "H2Ne"),
-            Self::ExistingN2N => write!(f, "N2Ne"),
-        }
+        write!(
+            f,
+            "{}{}",
+            self.pair_name(),
+            if self.existing { "e" } else { "n" }
+        )
+    }
+}
+
+impl FromStr for FlightType {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pair, existing) = if let Some(pair) = s.strip_suffix('e') {
+            (pair, true)
+        } else if let Some(pair) = s.strip_suffix('n') {
+            (pair, false)
+        } else {
+            return Err(anyhow!("Invalid flight type `{s}`"));
+        };
+        let (tier1, tier2) = pair
+            .split_once('2')
+            .ok_or_else(|| anyhow!("Invalid flight type `{s}`"))?;
+        Ok(Self {
+            tier1: tier1.into(),
+            tier2: tier2.into(),
+            existing,
+        })
     }
 }
 
 impl FlightType {
-    pub const fn score(self) -> i8 {
-        match self {
-            Self::NonExistingH2H => 6,
-            Self::ExistingH2H => 5,
-            Self::NonExistingH2N => 3,
-            Self::NonExistingN2N => 2,
-            Self::ExistingH2N => 1,
-            Self::ExistingN2N => -1,
-        }
+    /// The name of the flight type without whether it is existing, e.g. `H2N`
+    pub fn pair_name(&self) -> String {
+        format!("{}2{}", self.tier1, self.tier2)
     }
 }
@@ -1,5 +1,5 @@
 use crate::{
-    types::{flight_type::FlightType, gate::Gate, AirportCode},
+    types::{flight_type::FlightType, gate::Gate, rule::Route, size, AirportCode},
     Config, FlightData,
 };
 
@@ -12,85 +12,49 @@ pub trait FlightUtils {
     ) -> anyhow::Result<FlightType>;
 }
 
-impl FlightUtils for (&AirportCode, &AirportCode) {
-    fn score(&self, config: &mut Config, flight_data: &FlightData) -> anyhow::Result<i8> {
-        let mut s = 0i8;
-
-        s -= flight_data.num_flights(self.0, self.1) as i8 - 1;
-        if s == 1 {
-            s += 1;
-        }
+/// The score of a flight between two airports, without the score of its flight type and rules
+fn base_score(airports: (&AirportCode, &AirportCode), flight_data: &FlightData) -> i8 {
+    let (a1, a2) = airports;
+    let mut s = 0i8;
 
-        s += self.get_flight_type(config, flight_data)?.score();
+    s -= flight_data.num_flights(a1, a2) as i8 - 1;
+    if s == 1 {
+        s += 1;
+    }
 
-        if config
-            .preferred_between
-            .iter()
-            .any(|fs| fs.contains(self.0) && fs.contains(self.1))
-        {
-            s += 20;
-        }
-        if let Some(dests) = config.preferred_to.get(self.0) {
-            if dests.contains(self.1) {
-                s += 20;
-            }
-        }
-        if let Some(dests) = config.preferred_to.get(self.1) {
-            if dests.contains(self.0) {
-                s += 20;
-            }
-        }
+    if flight_data.old_world_airports.contains(a1) != flight_data.old_world_airports.contains(a2) {
+        s += 3;
+    }
 
-        if flight_data.old_world_airports.contains(self.0)
-            != flight_data.old_world_airports.contains(self.1)
-        {
-            s += 3;
-        }
+    s
+}
 
-        Ok(s)
+impl FlightUtils for (&AirportCode, &AirportCode) {
+    fn score(&self, config: &mut Config, flight_data: &FlightData) -> anyhow::Result<i8> {
+        let route = Route::between_airports(config, flight_data, self.0, self.1)?;
+        let rules = config.judge(&route).score;
+        Ok((base_score(*self, flight_data) + config.type_score(&route.ty)).saturating_add(rules))
     }
 
-    //noinspection DuplicatedCode
     fn get_flight_type(
         &self,
         config: &mut Config,
         flight_data: &FlightData,
     ) -> anyhow::Result<FlightType> {
-        Ok(if config.hubs()?.contains(self.0) {
-            if config.hubs()?.contains(self.1) {
-                if flight_data.num_flights(self.0, self.1) > 0 {
-                    FlightType::ExistingH2H
-                } else {
-                    FlightType::NonExistingH2H
-                }
-            } else if flight_data.num_flights(self.0, self.1) > 0 {
-                FlightType::ExistingH2N
-            } else {
-                FlightType::NonExistingH2N
-            }
-        } else if config.hubs()?.contains(self.1) {
-            if flight_data.num_flights(self.0, self.1) > 0 {
-                FlightType::ExistingH2N
-            } else {
-                FlightType::NonExistingH2N
-            }
-        } else if flight_data.num_flights(self.0, self.1) > 0 {
-            FlightType::ExistingN2N
-        } else {
-            FlightType::NonExistingN2N
-        })
+        let (tier1, tier2) = (config.airport_tier(self.0)?, config.airport_tier(self.1)?);
+        Ok(config.flight_type(tier1, tier2, flight_data.num_flights(self.0, self.1) > 0))
     }
 }
 
 impl FlightUtils for (&Gate, &Gate) {
     fn score(&self, config: &mut Config, flight_data: &FlightData) -> anyhow::Result<i8> {
-        let mut s = (&self.0.airport, &self.1.airport).score(config, flight_data)?;
-        if &*self.0.size != "S" {
-            s += 2;
-        }
-        if &*self.0.size == "XS" {
-            s += 1;
-        }
+        let route = Route::between_gates(config, flight_data, self.0, self.1)?;
+        let mut s = base_score((&self.0.airport, &self.1.airport), flight_data)
+            + config.type_score(&route.ty);
+        let plane =
+            size::plane_size(&config.sizes, self.0, self.1).unwrap_or_else(|| self.0.size.clone());
+        s += size::size_score(&config.sizes, &plane);
+        s = s.saturating_add(config.judge(&route).score);
 
         Ok(s)
     }
@@ -99,6 +63,12 @@ impl FlightUtils for (&Gate, &Gate) {
         config: &mut Config,
         flight_data: &FlightData,
     ) -> anyhow::Result<FlightType> {
-        (&self.0.airport, &self.1.airport).get_flight_type(config, flight_data)
+        let tier1 = config.tier(&self.0.airport, &self.0.size)?;
+        let tier2 = config.tier(&self.1.airport, &self.1.size)?;
+        Ok(config.flight_type(
+            tier1,
+            tier2,
+            flight_data.num_flights(&self.0.airport, &self.1.airport) > 0,
+        ))
     }
 }
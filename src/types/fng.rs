@@ -1,4 +1,8 @@
-use crate::types::FlightNumber;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::types::{config::Config, flight_type::FlightType, AirportCode, FlightNumber};
 
 pub struct FlightNumberGenerator(Box<dyn Iterator<Item = FlightNumber>>);
 
@@ -13,3 +17,39 @@ impl Iterator for FlightNumberGenerator {
         self.0.next()
     }
 }
+
+/// The flight number generators for each flight type,
+/// and for each airport if the type has separate ranges per airport
+#[derive(Default)]
+pub struct FlightNumberGenerators(HashMap<(String, Option<AirportCode>), FlightNumberGenerator>);
+
+impl FlightNumberGenerators {
+    /// The generator for a flight of type `ty` between the two airports.
+    /// If both airports have their own range, the first airport's range is used
+    pub fn get(
+        &mut self,
+        config: &Config,
+        ty: &FlightType,
+        airport1: &AirportCode,
+        airport2: &AirportCode,
+    ) -> Result<&mut FlightNumberGenerator> {
+        let pair = config
+            .tier_pair(ty)
+            .ok_or_else(|| anyhow!("No settings for {} flights", ty.pair_name()))?;
+        let airport = [airport1, airport2]
+            .into_iter()
+            .find(|a| pair.range_per_airport.contains_key(*a));
+        let range = airport.map_or(&pair.range, |a| &pair.range_per_airport[a]);
+        if range.is_empty() {
+            return Err(anyhow!(
+                "Flight number range not specified for {} flights between {airport1} and {airport2}",
+                ty.pair_name()
+            ));
+        }
+        let range = range.to_owned();
+        Ok(self
+            .0
+            .entry((ty.pair_name(), airport.cloned()))
+            .or_insert_with(|| FlightNumberGenerator::new(range)))
+    }
+}
@@ -1,14 +1,23 @@
 use std::fmt::Display;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{AirportCode, GateCode, Size};
+use crate::types::{size, AirportCode, GateCode, Size, Tag};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Gate {
     pub airport: AirportCode,
     pub code: GateCode,
     pub size: Size,
+    /// Free-form labels for the gate
+    pub tags: Vec<Tag>,
+    /// The maximum number of destinations of the gate, overriding `max_dests_per_gate`
+    pub max_dests: Option<u8>,
+    /// The minimum number of destinations of the gate, overriding `min_dests_per_gate`
+    pub min_dests: Option<u8>,
+    /// A note about the gate, which the planner does not use
+    pub note: Option<String>,
 }
 
 impl Display for Gate {
@@ -17,8 +26,42 @@ impl Display for Gate {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct PartialGate {
+    /// The code of the gate
     pub code: GateCode,
+    /// The size of the gate
+    #[schemars(schema_with = "size::schema")]
     pub size: Size,
+    /// Whether the gate is left out of the plan
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    /// Free-form labels for the gate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+    /// The maximum number of destinations of the gate, overriding `max_dests_per_gate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_dests: Option<u8>,
+    /// The minimum number of destinations of the gate, overriding `min_dests_per_gate`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dests: Option<u8>,
+    /// A note about the gate, which the planner does not use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl PartialGate {
+    /// The gate at `airport`
+    pub fn gate(&self, airport: &AirportCode) -> Gate {
+        Gate {
+            airport: airport.to_owned(),
+            code: self.code.clone(),
+            size: self.size.clone(),
+            tags: self.tags.clone(),
+            max_dests: self.max_dests,
+            min_dests: self.min_dests,
+            note: self.note.clone(),
+        }
+    }
 }
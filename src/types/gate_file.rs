@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{anyhow, Result};
+
+use crate::types::{
+    config_error::ConfigError,
+    gate::{Gate, PartialGate},
+    AirportCode,
+};
+
+/// The columns of a CSV or TSV gate file that must be given
+const REQUIRED_COLUMNS: [&str; 3] = ["airport", "code", "size"];
+
+/// Reads the gates in a gate file, leaving out disabled gates. The format depends on the extension:
+/// - `.csv` and `.tsv`: a header row naming the columns, which are `airport`, `code` (or `gate`)
+///   and `size`, and optionally `disabled`, `tags`, `max_dests`, `min_dests` and `note`. Other columns are ignored
+/// - `.yml`, `.yaml` and `.json`: a mapping of airport codes to lists of gates, as in `gates`
+/// - anything else: one `<airport> <gate_no> <size>` row for each gate
+pub fn read(file: &Path) -> Result<Vec<Gate>> {
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| anyhow!("Could not read gate file {}: {e}", file.display()))?;
+    let extension = file
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("csv") => read_table(file, &source, b','),
+        Some("tsv") => read_table(file, &source, b'\t'),
+        Some("yml" | "yaml" | "json") => {
+            let gates =
+                serde_yaml::from_str(&source).map_err(|e| ConfigError::new(file, &source, &e))?;
+            Ok(from_partial(&gates))
+        }
+        _ => read_text(file, &source),
+    }
+}
+
+/// The gates that are not disabled in a mapping of airport codes to lists of gates
+pub fn from_partial(gates: &BTreeMap<AirportCode, Vec<PartialGate>>) -> Vec<Gate> {
+    gates
+        .iter()
+        .flat_map(|(a, pgs)| pgs.iter().filter(|pg| !pg.disabled).map(|pg| pg.gate(a)))
+        .collect()
+}
+
+/// Reads a gate file with one `<airport> <gate_no> <size>` row for each gate
+fn read_text(file: &Path, source: &str) -> Result<Vec<Gate>> {
+    let mut gates = vec![];
+    for (i, line) in source.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let [airport, code, size, ..] = line.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(ConfigError::at_line(
+                file,
+                source,
+                i + 1,
+                "Expected a row of `<airport> <gate_no> <size>`".into(),
+                Some("separate the airport code, gate code and size with spaces".into()),
+            )
+            .into());
+        };
+        gates.push(Gate {
+            airport: airport.into(),
+            code: code.into(),
+            size: size.into(),
+            tags: vec![],
+            max_dests: None,
+            min_dests: None,
+            note: None,
+        });
+    }
+    Ok(gates)
+}
+
+/// Reads a gate file of rows separated by `delimiter`, with a header row
+#[expect(clippy::too_many_lines)]
+fn read_table(file: &Path, source: &str, delimiter: u8) -> Result<Vec<Gate>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(source.as_bytes());
+    let headers = reader
+        .headers()?
+        .iter()
+        .map(|h| match h.to_lowercase().as_str() {
+            "gate" => "code".to_owned(),
+            h => h.to_owned(),
+        })
+        .collect::<Vec<_>>();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let [Some(airport), Some(code), Some(size)] = REQUIRED_COLUMNS.map(column) else {
+        return Err(ConfigError::at_line(
+            file,
+            source,
+            1,
+            "The header row must name the `airport`, `code` and `size` columns".into(),
+            Some(format!(
+                "the optional columns are `disabled`, `tags`, `max_dests`, `min_dests` and `note`, found: {}",
+                headers.join(", ")
+            )),
+        )
+        .into());
+    };
+    let (disabled, tags, max_dests, min_dests, note) = (
+        column("disabled"),
+        column("tags"),
+        column("max_dests"),
+        column("min_dests"),
+        column("note"),
+    );
+
+    let mut gates = vec![];
+    for record in reader.records() {
+        let record = record.map_err(|e| {
+            let line = e.position().map_or(1, csv::Position::line);
+            ConfigError::at_line(file, source, line as usize, e.to_string(), None)
+        })?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let line = record.position().map_or(1, csv::Position::line) as usize;
+        let error = |message: String, hint: &str| {
+            ConfigError::at_line(file, source, line, message, Some(hint.into()))
+        };
+        let get = |column: Option<usize>| column.and_then(|i| record.get(i)).unwrap_or_default();
+        for (name, i) in REQUIRED_COLUMNS.iter().zip([airport, code, size]) {
+            if get(Some(i)).is_empty() {
+                return Err(error(
+                    format!("Missing `{name}`"),
+                    "every row must have an airport, gate code and size",
+                )
+                .into());
+            }
+        }
+        let is_disabled = match get(disabled).to_lowercase().as_str() {
+            "" | "false" | "no" | "n" | "0" => false,
+            "true" | "yes" | "y" | "1" | "x" => true,
+            other => {
+                return Err(error(
+                    format!("Invalid value `{other}` for `disabled`"),
+                    "this must be `true` or `false`, or left empty for `false`",
+                )
+                .into())
+            }
+        };
+        if is_disabled {
+            continue;
+        }
+        let [max_dests, min_dests] =
+            [(max_dests, "max_dests"), (min_dests, "min_dests")].map(|(column, name)| {
+                match get(column) {
+                    "" => Ok(None),
+                    n => n.parse().map(Some).map_err(|_| {
+                        error(
+                            format!("Invalid value `{n}` for `{name}`"),
+                            &format!(
+                            "this must be a whole number from 0 to 255, or left empty for no {}",
+                            if name == "max_dests" { "maximum" } else { "minimum" }
+                        ),
+                        )
+                    }),
+                }
+            });
+        gates.push(Gate {
+            airport: get(Some(airport)).into(),
+            code: get(Some(code)).into(),
+            size: get(Some(size)).into(),
+            tags: get(tags)
+                .split([',', ';'])
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(Into::into)
+                .collect(),
+            max_dests: max_dests?,
+            min_dests: min_dests?,
+            note: Some(get(note))
+                .filter(|n| !n.is_empty())
+                .map(ToOwned::to_owned),
+        });
+    }
+    Ok(gates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(airport: &str, code: &str, size: &str) -> Gate {
+        Gate {
+            airport: airport.into(),
+            code: code.into(),
+            size: size.into(),
+            tags: vec![],
+            max_dests: None,
+            min_dests: None,
+            note: None,
+        }
+    }
+
+    /// The line of the error in reading `source` as a CSV file
+    fn error_line(source: &str) -> Option<usize> {
+        let error = read_table(Path::new("gates.csv"), source, b',').unwrap_err();
+        error
+            .downcast_ref::<ConfigError>()?
+            .location
+            .map(|(line, _)| line)
+    }
+
+    #[test]
+    fn reads_csv() {
+        let source = "Airport, Gate, Size, Tags, Max_Dests, Disabled, Owner, Note
+AAA, A1, S,\"quiet, west\", 3, , Bob, by the river
+AAA, A2, S, , , yes, Bob,
+
+BBB, B1, M
+";
+        let mut a1 = gate("AAA", "A1", "S");
+        a1.tags = vec!["quiet".into(), "west".into()];
+        a1.max_dests = Some(3);
+        a1.note = Some("by the river".into());
+        assert_eq!(
+            read_table(Path::new("gates.csv"), source, b',').unwrap(),
+            [a1, gate("BBB", "B1", "M")]
+        );
+    }
+
+    #[test]
+    fn reads_tsv() {
+        let source = "code\tsize\tairport\nA1\tS\tAAA\n";
+        assert_eq!(
+            read_table(Path::new("gates.tsv"), source, b'\t').unwrap(),
+            [gate("AAA", "A1", "S")]
+        );
+    }
+
+    #[test]
+    fn locates_csv_errors() {
+        assert_eq!(error_line("airport,size\nAAA,S\n"), Some(1));
+        assert_eq!(error_line("airport,code,size\nAAA,A1,S\nAAA,,S\n"), Some(3));
+        assert_eq!(
+            error_line("airport,code,size,disabled\nAAA,A1,S,maybe\n"),
+            Some(2)
+        );
+        assert_eq!(
+            error_line("airport,code,size,max_dests\nAAA,A1,S,-1\n"),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn reads_text() {
+        let source = "AAA A1 S\n\nBBB  B1\tM extra\n";
+        assert_eq!(
+            read_text(Path::new("gates.txt"), source).unwrap(),
+            [gate("AAA", "A1", "S"), gate("BBB", "B1", "M")]
+        );
+        read_text(Path::new("gates.txt"), "AAA A1\n").unwrap_err();
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
+
+use crate::types::{config::Config, flight_data::FlightData, AirportCode, GroupName};
+
+/// Airport lists refer to a group with this before its name, e.g. `$london`
+pub const GROUP_PREFIX: char = '$';
+
+/// The named groups of airports from `groups`, and the ones built from Gatelogue:
+/// `world:old` and `world:new` for the airports in each world,
+/// and `town:<name>` for the airports nearest to each town
+pub struct Groups {
+    /// The airports in each group
+    airports: BTreeMap<GroupName, Vec<AirportCode>>,
+    /// The groups that were referred to in the config
+    used: BTreeSet<GroupName>,
+    /// Whether the groups from Gatelogue are known
+    online: bool,
+}
+
+impl Groups {
+    /// The groups of `config`, with the ones built from Gatelogue only containing airports with gates
+    pub fn new(config: &mut Config, fd: Option<&FlightData>) -> Result<Self> {
+        let mut groups = BTreeMap::new();
+        if let Some(fd) = fd {
+            let airports = config.airports()?;
+            let with_gates = |list: &[AirportCode]| {
+                list.iter()
+                    .filter(|a| airports.contains(a))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            };
+            groups.insert("world:old".into(), with_gates(&fd.old_world_airports));
+            groups.insert("world:new".into(), with_gates(&fd.new_world_airports));
+            for (airport, town) in fd.towns.iter().filter(|(a, _)| airports.contains(a)) {
+                groups
+                    .entry(format!("town:{town}").into())
+                    .or_insert_with(Vec::new)
+                    .push(airport.clone());
+            }
+        }
+        let mut this = Self {
+            airports: groups,
+            used: BTreeSet::new(),
+            online: fd.is_some(),
+        };
+        for name in config.groups.keys() {
+            let airports = this.resolve(config, name, &mut vec![])?;
+            this.airports.insert(name.clone(), airports);
+        }
+        Ok(this)
+    }
+    /// The airports in the group `name` from `groups`, with the groups it refers to expanded.
+    /// `stack` holds the groups being resolved, to find groups that contain themselves
+    fn resolve(
+        &mut self,
+        config: &Config,
+        name: &GroupName,
+        stack: &mut Vec<GroupName>,
+    ) -> Result<Vec<AirportCode>> {
+        if stack.contains(name) {
+            return Err(anyhow!(
+                "Group `{name}` contains itself (through {})",
+                stack.iter().join(" -> ")
+            ));
+        }
+        let Some(members) = config.groups.get(name) else {
+            return self.get(name, "groups");
+        };
+        stack.push(name.clone());
+        let mut airports = vec![];
+        for member in members {
+            if let Some(group) = member.strip_prefix(GROUP_PREFIX) {
+                self.used.insert(group.into());
+                airports.extend(self.resolve(config, &group.into(), stack)?);
+            } else {
+                airports.push(member.clone());
+            }
+        }
+        stack.pop();
+        Ok(airports.into_iter().unique().collect())
+    }
+    /// The airports in the group `name`, which is referred to in `place`
+    fn get(&mut self, name: &str, place: &str) -> Result<Vec<AirportCode>> {
+        self.used.insert(name.into());
+        self.airports.get(name).cloned().ok_or_else(|| {
+            let offline =
+                if !self.online && (name.starts_with("world:") || name.starts_with("town:")) {
+                    " (groups from Gatelogue are not available offline)"
+                } else {
+                    ""
+                };
+            anyhow!("Unknown group `{GROUP_PREFIX}{name}` in {place}{offline}")
+        })
+    }
+    /// `list` with the groups in it replaced by their airports
+    pub fn expand(&mut self, list: &[AirportCode], place: &str) -> Result<Vec<AirportCode>> {
+        let mut airports = vec![];
+        for a in list {
+            if let Some(name) = a.strip_prefix(GROUP_PREFIX) {
+                airports.extend(self.get(name, place)?);
+            } else {
+                airports.push(a.clone());
+            }
+        }
+        Ok(airports.into_iter().unique().collect())
+    }
+    /// `map` with the groups in its keys replaced by their airports. An airport in more than one key
+    /// gets the values combined with `merge`, with the value of the airport's own key merged last
+    pub fn expand_keys<V: Clone, F: Fn(&mut V, V)>(
+        &mut self,
+        map: &BTreeMap<AirportCode, V>,
+        place: &str,
+        merge: F,
+    ) -> Result<BTreeMap<AirportCode, V>> {
+        let mut expanded: BTreeMap<AirportCode, V> = BTreeMap::new();
+        let (groups, airports): (Vec<_>, Vec<_>) =
+            map.iter().partition(|(a, _)| a.starts_with(GROUP_PREFIX));
+        for (key, value) in groups.into_iter().chain(airports) {
+            for a in self.expand(std::slice::from_ref(key), place)? {
+                if let Some(existing) = expanded.get_mut(&a) {
+                    merge(existing, value.clone());
+                } else {
+                    expanded.insert(a, value.clone());
+                }
+            }
+        }
+        Ok(expanded)
+    }
+    /// The groups from `groups` and the worlds, and any other groups that were referred to,
+    /// for reporting statistics
+    pub fn reported(&self, config: &Config) -> BTreeMap<GroupName, Vec<AirportCode>> {
+        self.airports
+            .iter()
+            .filter(|(name, _)| {
+                config.groups.contains_key(*name)
+                    || name.starts_with("world:")
+                    || self.used.contains(*name)
+            })
+            .map(|(name, airports)| (name.clone(), airports.clone()))
+            .collect()
+    }
+}
+
+/// Replaces the groups in the airport lists of `config` with their airports
+pub fn resolve(config: &mut Config, fd: Option<&FlightData>) -> Result<()> {
+    let mut groups = Groups::new(config, fd)?;
+    let extend = |existing: &mut Vec<AirportCode>, new: Vec<AirportCode>| {
+        existing.extend(new);
+        *existing = existing.iter().unique().cloned().collect();
+    };
+
+    config.expand_groups(&mut groups)?;
+    for (place, sets) in [
+        ("restricted_between", &mut config.restricted_between),
+        ("preferred_between", &mut config.preferred_between),
+    ] {
+        for set in sets {
+            *set = groups.expand(set, place)?;
+        }
+    }
+    config.no_dupes = groups.expand(&config.no_dupes, "no_dupes")?;
+    for (place, map) in [
+        ("restricted_to", &mut config.restricted_to),
+        ("preferred_to", &mut config.preferred_to),
+    ] {
+        *map = groups.expand_keys(map, place, extend)?;
+        for dests in map.values_mut() {
+            *dests = groups.expand(dests, place)?;
+        }
+    }
+    for (place, map) in [
+        ("gate_allowed_dests", &mut config.gate_allowed_dests),
+        ("gate_denied_dests", &mut config.gate_denied_dests),
+    ] {
+        for dests in map.values_mut().flat_map(BTreeMap::values_mut) {
+            *dests = groups.expand(dests, place)?;
+        }
+    }
+    for (place, map) in [
+        ("max_dests_per_gate", &mut config.max_dests_per_gate),
+        ("min_dests", &mut config.min_dests),
+        ("min_dests_per_gate", &mut config.min_dests_per_gate),
+    ] {
+        *map = groups.expand_keys(map, place, |e, n| *e = n)?;
+    }
+    let overrides = &mut config.limit_overrides;
+    overrides.airports =
+        groups.expand_keys(&overrides.airports, "limit_overrides", |e, n| *e = n)?;
+    overrides.airport_sizes =
+        groups.expand_keys(&overrides.airport_sizes, "limit_overrides", |e, n| {
+            e.extend(n);
+        })?;
+    for rule in &mut config.tag_rules {
+        for dests in [
+            &mut rule.allowed_dests,
+            &mut rule.denied_dests,
+            &mut rule.preferred_dests,
+        ] {
+            *dests = groups.expand(dests, "tag_rules")?;
+        }
+    }
+    for rule in &mut config.rules {
+        for end in [&mut rule.from, &mut rule.to] {
+            end.airports = groups.expand(&end.airports, "rules")?;
+        }
+    }
+
+    config._groups = groups.reported(config);
+    Ok(())
+}
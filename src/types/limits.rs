@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+
+use smol_str::SmolStr;
+
+use crate::types::{size, AirportCode, Size, TierName};
+
+/// A value in [`Limits`] that can be overridden per gate size and per airport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit<'a> {
+    /// The hard maximum number of warps at a gate of the tier
+    HardMax {
+        tier: &'a TierName,
+    },
+    /// The maximum number of warps of a flight type (e.g. `H2N`) at a gate of the tier
+    TypeMax {
+        pair: &'a str,
+        tier: &'a TierName,
+    },
+    HubThreshold,
+    MaxDestsPerGate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    /// Overrides `hard_max_hub`
+    pub hard_max_hub: Option<u8>,
+    /// Overrides `hard_max_nonhub`
+    pub hard_max_nonhub: Option<u8>,
+    /// Overrides `max_h2h`
+    pub max_h2h: Option<u8>,
+    /// Overrides `max_h2n_hub`
+    pub max_h2n_hub: Option<u8>,
+    /// Overrides `max_h2n_nonhub`
+    pub max_h2n_nonhub: Option<u8>,
+    /// Overrides `max_n2n`
+    pub max_n2n: Option<u8>,
+    /// Overrides the hard maximum of the tier of the gate, taking precedence over `hard_max_XXX`
+    pub hard_max: Option<u8>,
+    /// Overrides the maximums of flight types (e.g. `H2N`) at the gate,
+    /// taking precedence over `max_XXX`
+    #[serde(default)]
+    pub max: BTreeMap<SmolStr, u8>,
+    /// Overrides `hub_threshold`
+    pub hub_threshold: Option<usize>,
+    /// Overrides `max_dests_per_gate`
+    pub max_dests_per_gate: Option<u8>,
+}
+impl Limits {
+    /// The value of `limit`. The hub/nonhub fields apply to the tiers named `H` and `N`,
+    /// but `hard_max` and `max` take precedence over them
+    pub fn get(&self, limit: Limit) -> Option<usize> {
+        match limit {
+            Limit::HardMax { tier } => self.hard_max.or(match &**tier {
+                "H" => self.hard_max_hub,
+                "N" => self.hard_max_nonhub,
+                _ => None,
+            }),
+            Limit::TypeMax { pair, tier } => {
+                self.max.get(pair).copied().or(match (pair, &**tier) {
+                    ("H2H", _) => self.max_h2h,
+                    ("H2N", "H") => self.max_h2n_hub,
+                    ("H2N", _) => self.max_h2n_nonhub,
+                    ("N2N", _) => self.max_n2n,
+                    _ => None,
+                })
+            }
+            Limit::HubThreshold => return self.hub_threshold,
+            Limit::MaxDestsPerGate => self.max_dests_per_gate,
+        }
+        .map(usize::from)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitOverrides {
+    /// Overrides for gates of each size
+    #[schemars(schema_with = "size::map_schema::<Limits>")]
+    pub sizes: BTreeMap<Size, Limits>,
+    /// Overrides for gates at each airport
+    pub airports: BTreeMap<AirportCode, Limits>,
+    /// Overrides for gates of each size at each airport
+    #[schemars(schema_with = "airport_sizes_schema")]
+    pub airport_sizes: BTreeMap<AirportCode, BTreeMap<Size, Limits>>,
+}
+fn airport_sizes_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": size::map_schema::<Limits>(generator)
+    })
+}
+impl LimitOverrides {
+    /// The override for gates of size `size` at `airport`, with the most specific one winning
+    /// (airport and size, then airport, then size)
+    pub fn get(&self, limit: Limit, airport: &AirportCode, size: &Size) -> Option<usize> {
+        self.get_for_size(limit, airport, size)
+            .or_else(|| self.airports.get(airport)?.get(limit))
+            .or_else(|| self.sizes.get(size)?.get(limit))
+    }
+    /// The override for gates of size `size` at `airport`, only considering size-specific overrides
+    pub fn get_for_size(&self, limit: Limit, airport: &AirportCode, size: &Size) -> Option<usize> {
+        self.airport_sizes.get(airport)?.get(size)?.get(limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(yaml: &str) -> Limits {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn legacy_fields_apply_to_h_and_n() {
+        let l = limits("{hard_max_hub: 1, hard_max_nonhub: 2, max_h2n_hub: 3, max_h2n_nonhub: 4, max_h2h: 5, max_n2n: 6}");
+        let (h, n, m) = ("H".into(), "N".into(), "M".into());
+        assert_eq!(l.get(Limit::HardMax { tier: &h }), Some(1));
+        assert_eq!(l.get(Limit::HardMax { tier: &n }), Some(2));
+        assert_eq!(l.get(Limit::HardMax { tier: &m }), None);
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2N",
+                tier: &h
+            }),
+            Some(3)
+        );
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2N",
+                tier: &n
+            }),
+            Some(4)
+        );
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2H",
+                tier: &h
+            }),
+            Some(5)
+        );
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "N2N",
+                tier: &n
+            }),
+            Some(6)
+        );
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2M",
+                tier: &h
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn hard_max_and_max_take_precedence() {
+        let l = limits("{hard_max_hub: 1, hard_max: 7, max_h2h: 5, max: {H2H: 8, H2M: 9}}");
+        let (h, m) = ("H".into(), "M".into());
+        assert_eq!(l.get(Limit::HardMax { tier: &h }), Some(7));
+        assert_eq!(l.get(Limit::HardMax { tier: &m }), Some(7));
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2H",
+                tier: &h
+            }),
+            Some(8)
+        );
+        assert_eq!(
+            l.get(Limit::TypeMax {
+                pair: "H2M",
+                tier: &m
+            }),
+            Some(9)
+        );
+    }
+
+    #[test]
+    fn most_specific_override_wins() {
+        let overrides: LimitOverrides = serde_yaml::from_str(
+            "{sizes: {S: {hub_threshold: 1}}, airports: {AAA: {hub_threshold: 2}}, airport_sizes: {AAA: {S: {hub_threshold: 3}}}}",
+        )
+        .unwrap();
+        let (aaa, bbb, s, m) = ("AAA".into(), "BBB".into(), "S".into(), "M".into());
+        assert_eq!(overrides.get(Limit::HubThreshold, &aaa, &s), Some(3));
+        assert_eq!(overrides.get(Limit::HubThreshold, &aaa, &m), Some(2));
+        assert_eq!(overrides.get(Limit::HubThreshold, &bbb, &s), Some(1));
+        assert_eq!(overrides.get(Limit::HubThreshold, &bbb, &m), None);
+    }
+}
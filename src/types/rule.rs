@@ -0,0 +1,492 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    config::Config, flight_data::FlightData, flight_type::FlightType, flight_utils::FlightUtils,
+    gate::Gate, size, AirportCode, GateCode, Size, Tag, TierName,
+};
+
+/// The score bonus of `preferred_between` and `preferred_to`
+const PREFERRED_SCORE: i8 = 20;
+
+/// The ends of routes that a rule matches. Every condition that is given must hold,
+/// and conditions left empty match anything
+#[derive(Debug, Serialize, Deserialize, Clone, Default, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Endpoint {
+    /// Airports (or groups) at this end
+    pub airports: Vec<AirportCode>,
+    /// Codes of the gates at this end
+    pub gates: Vec<GateCode>,
+    /// The gate at this end has at least one of these tags
+    pub tags: Vec<Tag>,
+    /// Sizes of the gate at this end
+    #[schemars(schema_with = "size::list_schema")]
+    pub sizes: Vec<Size>,
+    /// Tiers (e.g. `H`) of the gate at this end
+    pub tiers: Vec<TierName>,
+    /// The airport at this end is within this many blocks of the other end,
+    /// using the coordinates of the airports in Gatelogue
+    pub max_distance: Option<u32>,
+}
+impl Endpoint {
+    /// Whether `stop` matches, on a route whose airports are `distance` apart.
+    /// Conditions on the gate never match a route between airports
+    fn matches(&self, stop: &Stop, distance: Option<f64>) -> bool {
+        let gate_matches = |f: &dyn Fn(&Gate) -> bool| stop.gate.is_some_and(f);
+        (self.airports.is_empty() || self.airports.contains(stop.airport))
+            && (self.gates.is_empty() || gate_matches(&|g| self.gates.contains(&g.code)))
+            && (self.tags.is_empty()
+                || gate_matches(&|g| g.tags.iter().any(|t| self.tags.contains(t))))
+            && (self.sizes.is_empty() || gate_matches(&|g| self.sizes.contains(&g.size)))
+            && (self.tiers.is_empty() || self.tiers.contains(&stop.tier))
+            && self
+                .max_distance
+                .is_none_or(|max| distance.is_some_and(|d| d <= f64::from(max)))
+    }
+    /// Whether the endpoint has no conditions, so it matches anything
+    pub fn is_empty(&self) -> bool {
+        self.airports.is_empty()
+            && self.gates.is_empty()
+            && self.tags.is_empty()
+            && self.sizes.is_empty()
+            && self.tiers.is_empty()
+            && self.max_distance.is_none()
+    }
+    fn airports(airports: &[AirportCode]) -> Self {
+        Self {
+            airports: airports.to_vec(),
+            ..Self::default()
+        }
+    }
+}
+
+/// What a rule does to the routes it matches, other than changing their score
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    /// The routes cannot be flown
+    Forbid,
+    /// Routes from `from` can only go to `to`
+    Require,
+}
+
+/// A rule matching routes by their ends and flight type, which either forbids them,
+/// requires them to go somewhere, or changes their score
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// A name for the rule, used in messages about it
+    #[serde(default)]
+    pub name: Option<String>,
+    /// One end of the routes. Routes are matched in both directions
+    #[serde(default)]
+    pub from: Endpoint,
+    /// The other end of the routes
+    #[serde(default)]
+    pub to: Endpoint,
+    /// Flight types of the routes, e.g. `H2N`, or `H2Ne` for only the ones other airlines fly
+    #[serde(default)]
+    pub types: Vec<String>,
+    /// Whether other airlines fly the routes (default: either)
+    #[serde(default)]
+    pub existing: Option<bool>,
+    /// Whether the rule forbids the routes or requires them to go to `to`
+    #[serde(default)]
+    pub effect: Option<Effect>,
+    /// The amount that the rule changes the score of the routes by
+    #[serde(default)]
+    pub score: Option<i8>,
+    /// Whether the rule's `effect` is skipped for routes that rules give a positive score
+    #[serde(default)]
+    pub unless_preferred: bool,
+}
+impl Rule {
+    /// A rule without conditions on the flight type of the routes
+    pub const fn new(
+        name: String,
+        from: Endpoint,
+        to: Endpoint,
+        effect: Option<Effect>,
+        score: Option<i8>,
+    ) -> Self {
+        Self {
+            name: Some(name),
+            from,
+            to,
+            types: vec![],
+            existing: None,
+            effect,
+            score,
+            unless_preferred: false,
+        }
+    }
+    /// Whether the flight type of `route` is one of `types`
+    fn type_matches(&self, ty: &FlightType) -> bool {
+        self.existing.is_none_or(|e| e == ty.existing)
+            && (self.types.is_empty()
+                || self.types.iter().any(|t| {
+                    let pair_matches = |pair: &str| {
+                        pair.split_once('2').is_some_and(|(t1, t2)| {
+                            (t1 == ty.tier1 && t2 == ty.tier2) || (t1 == ty.tier2 && t2 == ty.tier1)
+                        })
+                    };
+                    pair_matches(t)
+                        || t.strip_suffix('e')
+                            .is_some_and(|p| ty.existing && pair_matches(p))
+                        || t.strip_suffix('n')
+                            .is_some_and(|p| !ty.existing && pair_matches(p))
+                }))
+    }
+    /// Whether the rule matches `route` going from its stop `from` to the other stop
+    fn matches_from(&self, route: &Route, from: usize) -> bool {
+        self.type_matches(&route.ty)
+            && self.from.matches(&route.stops[from], route.distance)
+            && self.to.matches(&route.stops[1 - from], route.distance)
+    }
+    /// Whether the rule matches `route` in either direction
+    fn matches(&self, route: &Route) -> bool {
+        (0..2).any(|from| self.matches_from(route, from))
+    }
+    /// Whether the rule forbids `route`
+    fn forbids(&self, route: &Route) -> bool {
+        match self.effect {
+            Some(Effect::Forbid) => self.matches(route),
+            Some(Effect::Require) => (0..2).any(|from| {
+                self.type_matches(&route.ty)
+                    && self.from.matches(&route.stops[from], route.distance)
+                    && !self.to.matches(&route.stops[1 - from], route.distance)
+            }),
+            None => false,
+        }
+    }
+}
+
+/// One end of a route that rules are checked against
+pub struct Stop<'a> {
+    pub airport: &'a AirportCode,
+    /// The gate, if the route is between gates rather than airports
+    pub gate: Option<&'a Gate>,
+    pub tier: TierName,
+}
+
+/// A route that rules are checked against
+pub struct Route<'a> {
+    pub stops: [Stop<'a>; 2],
+    pub ty: FlightType,
+    /// The distance between the airports, if it is known
+    pub distance: Option<f64>,
+}
+impl<'a> Route<'a> {
+    /// The route between the gates `g1` and `g2`
+    pub fn between_gates(
+        config: &mut Config,
+        fd: &FlightData,
+        g1: &'a Gate,
+        g2: &'a Gate,
+    ) -> Result<Self> {
+        let mut route = Self::between_gates_without_data(config, g1, g2)?;
+        route.ty = (g1, g2).get_flight_type(config, fd)?;
+        route.distance = fd.distance(&g1.airport, &g2.airport);
+        Ok(route)
+    }
+    /// The route between the gates `g1` and `g2` without the flight data,
+    /// so other airlines are taken not to fly it and its distance is not known
+    pub fn between_gates_without_data(
+        config: &mut Config,
+        g1: &'a Gate,
+        g2: &'a Gate,
+    ) -> Result<Self> {
+        let (t1, t2) = (
+            config.tier(&g1.airport, &g1.size)?,
+            config.tier(&g2.airport, &g2.size)?,
+        );
+        Ok(Self {
+            stops: [(g1, t1), (g2, t2)].map(|(g, t)| Stop {
+                airport: &g.airport,
+                gate: Some(g),
+                tier: config.tier_name(t),
+            }),
+            ty: config.flight_type(t1, t2, false),
+            distance: None,
+        })
+    }
+    /// The route between the airports `a1` and `a2`, whatever gates it uses
+    pub fn between_airports(
+        config: &mut Config,
+        fd: &FlightData,
+        a1: &'a AirportCode,
+        a2: &'a AirportCode,
+    ) -> Result<Self> {
+        let (t1, t2) = (config.airport_tier(a1)?, config.airport_tier(a2)?);
+        Ok(Self {
+            stops: [(a1, t1), (a2, t2)].map(|(a, t)| Stop {
+                airport: a,
+                gate: None,
+                tier: config.tier_name(t),
+            }),
+            ty: (a1, a2).get_flight_type(config, fd)?,
+            distance: fd.distance(a1, a2),
+        })
+    }
+}
+
+/// What the rules decide about a route
+pub struct Verdict {
+    /// The name of the rule that forbids the route, if any
+    pub forbidden_by: Option<String>,
+    /// The sum of the scores of the rules that match the route
+    pub score: i8,
+}
+
+/// Applies `rules` to `route`
+pub fn judge(rules: &[Rule], route: &Route) -> Verdict {
+    let score = rules
+        .iter()
+        .filter_map(|r| r.score.filter(|_| r.matches(route)))
+        .fold(0i8, i8::saturating_add);
+    let forbidden_by = rules
+        .iter()
+        .filter(|r| !(r.unless_preferred && score > 0))
+        .find(|r| r.forbids(route))
+        .map(|r| r.name.clone().unwrap_or_default());
+    Verdict {
+        forbidden_by,
+        score,
+    }
+}
+
+/// The rules of `config`: the ones made from the restrictions and preferences in the other settings,
+/// followed by `rules`. Lists left empty in the other settings have no effect
+pub fn all_rules(config: &Config) -> Vec<Rule> {
+    let mut rules = vec![];
+    for set in config.restricted_between.iter().filter(|s| !s.is_empty()) {
+        rules.push(Rule::new(
+            format!("restricted_between [{}]", set.join(", ")),
+            Endpoint::airports(set),
+            Endpoint::airports(set),
+            Some(Effect::Forbid),
+            None,
+        ));
+    }
+    for (a, dests) in config.restricted_to.iter().filter(|(_, d)| !d.is_empty()) {
+        rules.push(Rule::new(
+            format!("restricted_to {a}"),
+            Endpoint::airports(std::slice::from_ref(a)),
+            Endpoint::airports(dests),
+            Some(Effect::Forbid),
+            None,
+        ));
+    }
+    for (place, map) in [
+        ("gate_allowed_dests", &config.gate_allowed_dests),
+        ("gate_denied_dests", &config.gate_denied_dests),
+    ] {
+        for (a, gates) in map {
+            for (code, dests) in gates {
+                let effect = match (place, dests.is_empty()) {
+                    // a gate allowed to fly nowhere is forbidden from flying anywhere
+                    ("gate_allowed_dests", true) => Effect::Forbid,
+                    ("gate_allowed_dests", false) => Effect::Require,
+                    (_, true) => continue,
+                    (_, false) => Effect::Forbid,
+                };
+                let from = Endpoint {
+                    gates: vec![code.clone()],
+                    ..Endpoint::airports(std::slice::from_ref(a))
+                };
+                rules.push(Rule::new(
+                    format!("{place} {a} {code}"),
+                    from,
+                    Endpoint::airports(dests),
+                    Some(effect),
+                    None,
+                ));
+            }
+        }
+    }
+    for rule in &config.tag_rules {
+        rules.extend(rule.rules());
+    }
+    for set in config.preferred_between.iter().filter(|s| !s.is_empty()) {
+        rules.push(Rule::new(
+            format!("preferred_between [{}]", set.join(", ")),
+            Endpoint::airports(set),
+            Endpoint::airports(set),
+            None,
+            Some(PREFERRED_SCORE),
+        ));
+    }
+    for (a, dests) in config.preferred_to.iter().filter(|(_, d)| !d.is_empty()) {
+        rules.push(Rule::new(
+            format!("preferred_to {a}"),
+            Endpoint::airports(std::slice::from_ref(a)),
+            Endpoint::airports(dests),
+            None,
+            Some(PREFERRED_SCORE),
+        ));
+    }
+    if !config.no_dupes.is_empty() {
+        rules.push(Rule {
+            existing: Some(true),
+            unless_preferred: true,
+            ..Rule::new(
+                "no_dupes".into(),
+                Endpoint::airports(&config.no_dupes),
+                Endpoint::default(),
+                Some(Effect::Forbid),
+                None,
+            )
+        });
+    }
+    for (i, rule) in config.rules.iter().enumerate() {
+        rules.push(Rule {
+            name: Some(rule.name.clone().unwrap_or_else(|| format!("rules[{i}]"))),
+            ..rule.clone()
+        });
+    }
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn gate(airport: &str, code: &str) -> Gate {
+        Gate {
+            airport: airport.into(),
+            code: code.into(),
+            size: "S".into(),
+            tags: vec![],
+            max_dests: None,
+            min_dests: None,
+            note: None,
+        }
+    }
+
+    /// The verdict of the rules of `config` on the route from `g1` to `g2`.
+    /// The planner used to pair gates in sorted order, so `g1` comes before `g2` in these tests
+    fn verdict(config: &Config, g1: &Gate, g2: &Gate, existing: bool) -> Verdict {
+        fn stop(g: &Gate) -> Stop<'_> {
+            Stop {
+                airport: &g.airport,
+                gate: Some(g),
+                tier: "N".into(),
+            }
+        }
+        let route = Route {
+            stops: [stop(g1), stop(g2)],
+            ty: FlightType {
+                tier1: "N".into(),
+                tier2: "N".into(),
+                existing,
+            },
+            distance: None,
+        };
+        judge(&all_rules(config), &route)
+    }
+
+    fn forbidden(config: &Config, g1: &Gate, g2: &Gate) -> bool {
+        verdict(config, g1, g2, false).forbidden_by.is_some()
+    }
+
+    fn gate_dests(dests: &[&str]) -> BTreeMap<AirportCode, BTreeMap<GateCode, Vec<AirportCode>>> {
+        BTreeMap::from([(
+            "AAA".into(),
+            BTreeMap::from([("A1".into(), dests.iter().map(|&d| d.into()).collect())]),
+        )])
+    }
+
+    #[test]
+    fn restricted_between() {
+        let mut config = Config::default();
+        config.restricted_between = vec![vec!["AAA".into(), "BBB".into()]];
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+        assert!(!forbidden(&config, &gate("AAA", "A1"), &gate("CCC", "C1")));
+        assert!(!forbidden(&config, &gate("BBB", "B1"), &gate("CCC", "C1")));
+    }
+
+    #[test]
+    fn restricted_to() {
+        let mut config = Config::default();
+        config.restricted_to = BTreeMap::from([("AAA".into(), vec!["BBB".into()])]);
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+        assert!(!forbidden(&config, &gate("AAA", "A1"), &gate("CCC", "C1")));
+        // the planner used to only check the first gate of the pair, but flights go both ways
+        config.restricted_to = BTreeMap::from([("BBB".into(), vec!["AAA".into()])]);
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+    }
+
+    #[test]
+    fn gate_allowed_dests() {
+        let mut config = Config::default();
+        config.gate_allowed_dests = gate_dests(&["BBB"]);
+        assert!(!forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("CCC", "C1")));
+        assert!(!forbidden(&config, &gate("AAA", "A2"), &gate("CCC", "C1")));
+        assert!(!forbidden(&config, &gate("BBB", "B1"), &gate("CCC", "C1")));
+
+        // a gate allowed to fly nowhere cannot fly anywhere
+        config.gate_allowed_dests = gate_dests(&[]);
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+        assert!(!forbidden(&config, &gate("AAA", "A2"), &gate("BBB", "B1")));
+    }
+
+    #[test]
+    fn gate_denied_dests() {
+        let mut config = Config::default();
+        config.gate_denied_dests = gate_dests(&["BBB"]);
+        assert!(forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+        assert!(!forbidden(&config, &gate("AAA", "A1"), &gate("CCC", "C1")));
+        assert!(!forbidden(&config, &gate("AAA", "A2"), &gate("BBB", "B1")));
+
+        config.gate_denied_dests = gate_dests(&[]);
+        assert!(!forbidden(&config, &gate("AAA", "A1"), &gate("BBB", "B1")));
+    }
+
+    #[test]
+    fn no_dupes() {
+        let mut config = Config::default();
+        config.no_dupes = vec!["AAA".into()];
+        let (a, b, c) = (gate("AAA", "A1"), gate("BBB", "B1"), gate("CCC", "C1"));
+        assert!(verdict(&config, &a, &b, true).forbidden_by.is_some());
+        assert!(verdict(&config, &a, &b, false).forbidden_by.is_none());
+        assert!(verdict(&config, &b, &c, true).forbidden_by.is_none());
+
+        // preferred routes are allowed to be duplicates, whichever end prefers the other
+        for (from, to) in [("AAA", "BBB"), ("BBB", "AAA")] {
+            config.preferred_to = BTreeMap::from([(from.into(), vec![to.into()])]);
+            let v = verdict(&config, &a, &b, true);
+            assert!(v.forbidden_by.is_none());
+            assert_eq!(v.score, PREFERRED_SCORE);
+        }
+        config.preferred_to = BTreeMap::new();
+        config.preferred_between = vec![vec!["AAA".into(), "BBB".into()]];
+        assert!(verdict(&config, &a, &b, true).forbidden_by.is_none());
+    }
+
+    #[test]
+    fn preferred_scores() {
+        let mut config = Config::default();
+        let (a, b, c) = (gate("AAA", "A1"), gate("BBB", "B1"), gate("CCC", "C1"));
+        assert_eq!(verdict(&config, &a, &b, false).score, 0);
+
+        config.preferred_between = vec![vec!["AAA".into(), "BBB".into()]];
+        assert_eq!(verdict(&config, &a, &b, false).score, 20);
+        assert_eq!(verdict(&config, &a, &c, false).score, 0);
+
+        config.preferred_between = vec![];
+        config.preferred_to = BTreeMap::from([("AAA".into(), vec!["BBB".into()])]);
+        assert_eq!(verdict(&config, &a, &b, false).score, 20);
+        assert_eq!(verdict(&config, &a, &c, false).score, 0);
+
+        // each of `preferred_between` and both ends' `preferred_to` adds the score
+        config.preferred_to.insert("BBB".into(), vec!["AAA".into()]);
+        assert_eq!(verdict(&config, &a, &b, false).score, 40);
+        config.preferred_between = vec![vec!["AAA".into(), "BBB".into()]];
+        assert_eq!(verdict(&config, &a, &b, false).score, 60);
+    }
+}
@@ -0,0 +1,107 @@
+use itertools::Itertools;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{gate::Gate, Size};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SizeConfig {
+    /// The name of the size
+    #[schemars(schema_with = "self::schema")]
+    pub name: Size,
+    /// The score bonus for flights with planes of this size
+    #[serde(default)]
+    pub score: i8,
+    /// The other gate sizes that planes of this size can also use
+    #[serde(default)]
+    #[schemars(schema_with = "self::list_schema")]
+    pub fits: Vec<Size>,
+}
+
+/// The gate sizes suggested in the JSON Schema when the config's own sizes are not known
+pub const COMMON_SIZES: [&str; 4] = ["XS", "S", "M", "L"];
+
+/// The JSON Schema name of gate sizes, so that they can be replaced with the sizes of a config
+pub const SCHEMA_NAME: &str = "Size";
+
+/// Gate sizes in the JSON Schema
+struct SizeSchema;
+impl JsonSchema for SizeSchema {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        SCHEMA_NAME.into()
+    }
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "The size of a gate",
+            "anyOf": [{ "enum": COMMON_SIZES }, { "type": "string" }]
+        })
+    }
+}
+
+/// The JSON Schema of a gate size
+pub fn schema(generator: &mut SchemaGenerator) -> Schema {
+    generator.subschema_for::<SizeSchema>()
+}
+/// The JSON Schema of a list of gate sizes
+pub fn list_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({ "type": "array", "items": schema(generator) })
+}
+/// The JSON Schema of a mapping of gate sizes to `T`
+pub fn map_schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "propertyNames": schema(generator),
+        "additionalProperties": generator.subschema_for::<T>()
+    })
+}
+
+/// Whether a plane of size `plane` can use a gate of size `gate`
+pub fn fits(sizes: &[SizeConfig], plane: &Size, gate: &Size) -> bool {
+    plane == gate
+        || sizes
+            .iter()
+            .any(|s| s.name == *plane && s.fits.contains(gate))
+}
+
+/// The size of plane that flies between the two gates, or `None` if no plane can use both.
+/// If more than one size of plane can be used, the one with the highest score is picked
+pub fn plane_size(sizes: &[SizeConfig], g1: &Gate, g2: &Gate) -> Option<Size> {
+    if sizes.is_empty() {
+        return (g1.size == g2.size).then(|| g1.size.clone());
+    }
+    sizes
+        .iter()
+        .filter(|s| fits(sizes, &s.name, &g1.size) && fits(sizes, &s.name, &g2.size))
+        .rev()
+        .max_by_key(|s| s.score)
+        .map(|s| s.name.clone())
+}
+
+/// The bonus score for flights with planes of the given size
+pub fn size_score(sizes: &[SizeConfig], size: &Size) -> i8 {
+    if sizes.is_empty() {
+        return match &**size {
+            "S" => 0,
+            "XS" => 3,
+            _ => 2,
+        };
+    }
+    sizes
+        .iter()
+        .find(|s| s.name == *size)
+        .map_or(0, |s| s.score)
+}
+
+/// A known size that `size` may have been a typo of
+pub fn suggest<'a, I: IntoIterator<Item = &'a Size>>(known: I, size: &Size) -> Option<&'a Size> {
+    known
+        .into_iter()
+        .filter(|k| *k != size)
+        .find(|k| k.eq_ignore_ascii_case(size) || k.trim() == size.trim())
+}
+
+/// The declared names of all sizes, for messages
+pub fn names(sizes: &[SizeConfig]) -> String {
+    sizes.iter().map(|s| &s.name).join(", ")
+}
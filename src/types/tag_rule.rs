@@ -0,0 +1,129 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{
+    rule::{Effect, Endpoint, Rule},
+    AirportCode, Tag, TierName,
+};
+
+/// Restrictions and preferences for the flights from gates with a tag
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TagRule {
+    /// The tag of the gates that the rule applies to
+    pub tag: Tag,
+    /// The only airports that the gates can fly to (if empty, any airport)
+    #[serde(default)]
+    pub allowed_dests: Vec<AirportCode>,
+    /// The airports that the gates cannot fly to
+    #[serde(default)]
+    pub denied_dests: Vec<AirportCode>,
+    /// The gates can only fly to gates with at least one of these tags (if empty, any gate)
+    #[serde(default)]
+    pub allowed_tags: Vec<Tag>,
+    /// The gates cannot fly to gates with any of these tags
+    #[serde(default)]
+    pub denied_tags: Vec<Tag>,
+    /// The gates can only fly to airports within this many blocks,
+    /// using the coordinates of the airports in Gatelogue
+    #[serde(default)]
+    pub max_distance: Option<u32>,
+    /// Airports that the gates are preferred to fly to
+    #[serde(default)]
+    pub preferred_dests: Vec<AirportCode>,
+    /// Tiers (e.g. `H`) of the gates that the gates are preferred to fly to
+    #[serde(default)]
+    pub preferred_tiers: Vec<TierName>,
+    /// Tags of the gates that the gates are preferred to fly to
+    #[serde(default)]
+    pub preferred_tags: Vec<Tag>,
+    /// The score bonus for flights to preferred destinations. Each of the preferred lists
+    /// a destination is in adds the bonus, but a flight between two gates with the tag
+    /// gets each bonus only once
+    #[serde(default = "TagRule::default_score")]
+    pub score: i8,
+}
+impl TagRule {
+    const fn default_score() -> i8 {
+        20
+    }
+    /// The rules that this tag rule is made of: one for each of its restrictions
+    /// and one for each list of preferred destinations
+    pub fn rules(&self) -> Vec<Rule> {
+        let name = |place: &str| format!("tag_rules {} {place}", self.tag);
+        let from = || Endpoint {
+            tags: vec![self.tag.clone()],
+            ..Endpoint::default()
+        };
+        let to = [
+            (
+                "allowed_dests",
+                (Some(Effect::Require), None),
+                Endpoint {
+                    airports: self.allowed_dests.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "denied_dests",
+                (Some(Effect::Forbid), None),
+                Endpoint {
+                    airports: self.denied_dests.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "allowed_tags",
+                (Some(Effect::Require), None),
+                Endpoint {
+                    tags: self.allowed_tags.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "denied_tags",
+                (Some(Effect::Forbid), None),
+                Endpoint {
+                    tags: self.denied_tags.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "max_distance",
+                (Some(Effect::Require), None),
+                Endpoint {
+                    max_distance: self.max_distance,
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "preferred_dests",
+                (None, Some(self.score)),
+                Endpoint {
+                    airports: self.preferred_dests.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "preferred_tiers",
+                (None, Some(self.score)),
+                Endpoint {
+                    tiers: self.preferred_tiers.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+            (
+                "preferred_tags",
+                (None, Some(self.score)),
+                Endpoint {
+                    tags: self.preferred_tags.clone(),
+                    ..Endpoint::default()
+                },
+            ),
+        ];
+        to.into_iter()
+            .filter(|(_, _, to)| !to.is_empty())
+            .map(|(place, (effect, score), to)| Rule::new(name(place), from(), to, effect, score))
+            .collect()
+    }
+}
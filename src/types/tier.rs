@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AirportCode, FlightNumber, TierName};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Tier {
+    /// The name of the tier, in letters only, used in flight types (e.g. `M2Rn`)
+    pub name: TierName,
+    /// The airports in this tier
+    #[serde(default)]
+    pub airports: Vec<AirportCode>,
+    /// If `airports` is empty, the minimum number of gates for an airport to be in this tier
+    /// (if not given, every airport not in an earlier tier is in this tier)
+    #[serde(default)]
+    pub min_gates: Option<usize>,
+    /// The hard maximum number of warps in a plane at airports in this tier
+    pub hard_max: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TierPair {
+    /// The two tiers, e.g. `[M, R]`
+    pub tiers: (TierName, TierName),
+    /// The range of flight numbers for flights of this type
+    #[serde(default)]
+    pub range: Vec<(FlightNumber, FlightNumber)>,
+    /// The ranges of flight numbers for each airport in the first tier, used instead of `range`
+    #[serde(default)]
+    pub range_per_airport: BTreeMap<AirportCode, Vec<(FlightNumber, FlightNumber)>>,
+    /// The score bonus for flights of this type that no other airline flies
+    pub score: i8,
+    /// The score bonus for flights of this type that other airlines fly
+    pub existing_score: i8,
+    /// The maximum number of warps of this type in a plane, at the first and second tier
+    pub max: (u8, u8),
+}
+impl TierPair {
+    /// The name of the flight type of this pair without whether it is existing, e.g. `H2N`
+    pub fn name(&self) -> String {
+        format!("{}2{}", self.tiers.0, self.tiers.1)
+    }
+    pub fn matches(&self, tier1: &TierName, tier2: &TierName) -> bool {
+        (&self.tiers.0, &self.tiers.1) == (tier1, tier2)
+            || (&self.tiers.0, &self.tiers.1) == (tier2, tier1)
+    }
+    /// The maximum number of warps of this pair's flight type at a gate of tier `tier`
+    pub fn max_at(&self, tier: &TierName) -> u8 {
+        if self.tiers.0 == *tier {
+            self.max.0
+        } else {
+            self.max.1
+        }
+    }
+}
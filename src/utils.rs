@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 #[inline]
 #[expect(clippy::impl_trait_in_params)]
 pub fn for_both<T, U: Sized>(x: &T, y: &T, f: impl Fn(&T) -> U) -> (U, U) {
@@ -20,17 +22,49 @@ macro_rules! fbp {
     };
 }
 
-#[expect(dead_code)]
-pub trait AnyAllBool: Copy {
-    fn any(self) -> bool;
-    fn all(self) -> bool;
+/// A small FNV-1a hasher, used instead of [`std::collections::hash_map::DefaultHasher`]
+/// as its output is stable across Rust versions and platforms
+struct Fnv1a(u64);
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
 }
-impl AnyAllBool for (bool, bool) {
-    #[inline]
-    fn any(self) -> bool {
-        self.0 || self.1
+
+/// Hashes `value` with `seed`, giving a reproducible pseudo-random key for ordering
+pub fn seeded_hash<T: Hash>(seed: u64, value: &T) -> u64 {
+    let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325 ^ seed);
+    value.hash(&mut hasher);
+    // splitmix64 finaliser so that nearby seeds give unrelated orders
+    let mut z = hasher.finish().wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_hash_is_reproducible() {
+        assert_eq!(
+            seeded_hash(1, &("AAA", "A1")),
+            seeded_hash(1, &("AAA", "A1"))
+        );
+        // the order must not change between Rust versions or platforms
+        assert_eq!(seeded_hash(0, &0u8), 0x71b8_262b_b6e2_e086);
     }
-    fn all(self) -> bool {
-        self.0 && self.1
+
+    #[test]
+    fn seeded_hash_depends_on_seed_and_value() {
+        assert_ne!(seeded_hash(1, &"AAA"), seeded_hash(2, &"AAA"));
+        assert_ne!(seeded_hash(1, &"AAA"), seeded_hash(1, &"AAB"));
     }
 }